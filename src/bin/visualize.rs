@@ -0,0 +1,232 @@
+//! Debug visualizations of the puzzle models.
+//!
+//! ```text
+//! visualize dot <day> [input] [--ignore-slopes]
+//! visualize ppm|svg <day> [input] [--scale=N] [--cycles=N] [--program=NWSE] [--entry=X,Y,DIR]
+//!     [--ultra]
+//! visualize play <day> [input] [--fps=N] [--cycles=N] [--program=NWSE] [--entry=X,Y,DIR]
+//!     [--presses=N] [--steps=N]
+//! ```
+//!
+//! `play` animates the intermediate states of a simulation in the terminal using ANSI escapes.
+//!
+//! The input defaults to `input/2023/day<day>.txt`, where cargo-aoc stores the puzzle input.
+
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use aoc2023::common::{Direction, Grid, Rgb, Vec2i};
+use aoc2023::{day08, day10, day14, day16, day17, day18, day20, day21, day22, day23, day25};
+
+const USAGE: &str = "usage: visualize dot|ppm|svg|play <day> [input] [--flag[=value]...]";
+
+struct Args {
+    day: u32,
+    input: String,
+    flags: Vec<String>,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Self> {
+        let (flags, positional): (Vec<_>, Vec<_>) =
+            args.iter().cloned().partition(|a| a.starts_with("--"));
+        let (day, input) = match positional.as_slice() {
+            [day] => (day, None),
+            [day, input] => (day, Some(input.clone())),
+            _ => bail!(USAGE),
+        };
+        let day: u32 = day
+            .trim_start_matches("day")
+            .parse()
+            .with_context(|| format!("invalid day: {day}"))?;
+        let input = input.unwrap_or_else(|| format!("input/2023/day{day}.txt"));
+        let input = fs::read_to_string(&input).with_context(|| format!("reading {input}"))?;
+        Ok(Self { day, input, flags })
+    }
+
+    fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+
+    fn flag_value(&self, flag: &str) -> Option<&str> {
+        self.flags
+            .iter()
+            .find_map(|f| f.strip_prefix(flag)?.strip_prefix('='))
+    }
+
+    fn parsed_flag_value<T: std::str::FromStr>(&self, flag: &str, default: T) -> Result<T> {
+        self.flag_value(flag)
+            .map(|v| {
+                v.parse()
+                    .map_err(|_| anyhow!("invalid value for {flag}: {v}"))
+            })
+            .unwrap_or(Ok(default))
+    }
+}
+
+fn dot(args: &Args) -> Result<String> {
+    let input = args.input.as_str();
+    Ok(match args.day {
+        8 => day08::input_generator(input).to_dot(),
+        20 => day20::input_generator(input).to_dot(),
+        22 => day22::support_to_dot(&day22::input_generator(input)),
+        23 => day23::crossing_graph_to_dot(
+            &day23::input_generator(input),
+            args.has_flag("--ignore-slopes"),
+        ),
+        25 => day25::to_dot(&day25::input_generator(input)),
+        day => bail!("no graph export for day {day}"),
+    }
+    .to_string())
+}
+
+fn image(args: &Args) -> Result<Grid<Rgb>> {
+    let input = args.input.as_str();
+    Ok(match args.day {
+        10 => day10::render_loop(&day10::input_generator(input)),
+        14 => day14::render_platform(
+            &day14::input_generator(input),
+            &program(args)?,
+            args.parsed_flag_value("--cycles", 1_000_000_000)?,
+        ),
+        16 => day16::render_energized(&day16::input_generator(input), &entry(args)?),
+        17 => {
+            let (min_straight, max_straight) = if args.has_flag("--ultra") {
+                (4, 10)
+            } else {
+                (0, 3)
+            };
+            day17::render_shortest_path(&day17::input_generator(input), min_straight, max_straight)
+        }
+        18 => day18::render_trench(&day18::input_generator(input)),
+        day => bail!("no image export for day {day}"),
+    })
+}
+
+fn play(args: &Args) -> Result<()> {
+    let fps: f64 = args.parsed_flag_value("--fps", 10.0)?;
+    if fps.is_nan() || fps <= 0.0 {
+        bail!("invalid value for --fps: {fps}");
+    }
+    let frame_time = Duration::from_secs_f64(1.0 / fps);
+
+    let input = args.input.as_str();
+    match args.day {
+        14 => playback(
+            day14::program_frames(
+                &day14::input_generator(input),
+                &program(args)?,
+                args.parsed_flag_value("--cycles", 1)?,
+            )
+            .map(|grid| grid.to_string()),
+            frame_time,
+        ),
+        16 => {
+            let grid = day16::input_generator(input);
+            playback(
+                day16::beam_frames(&grid, &entry(args)?)
+                    .map(|frame| frame.render(&grid).to_string()),
+                frame_time,
+            )
+        }
+        20 => {
+            let mut presses = day20::input_generator(input).button_presses();
+            playback(
+                (1..=args.parsed_flag_value("--presses", 1000)?).map(move |i| {
+                    let pulses = presses.next().unwrap();
+                    let high = pulses.iter().filter(|p| p.high).count();
+                    format!(
+                        "press {i}: {} low, {high} high pulses\n\n{}",
+                        pulses.len() - high,
+                        presses.state().render_state()
+                    )
+                }),
+                frame_time,
+            )
+        }
+        21 => {
+            let (start, grid) = day21::input_generator(input);
+            playback(
+                day21::garden_steps(&grid, start)
+                    .take(args.parsed_flag_value("--steps", 64)? + 1)
+                    .map(|reachable| day21::render_reachable(&grid, &reachable).to_string()),
+                frame_time,
+            )
+        }
+        day => bail!("no playback for day {day}"),
+    }
+}
+
+/// Draws each frame over the previous one, waiting `frame_time` in between.
+fn playback(frames: impl Iterator<Item = String>, frame_time: Duration) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    // hide the cursor and clear the screen
+    write!(stdout, "\x1b[?25l\x1b[2J")?;
+    let result = frames.into_iter().try_for_each(|frame| -> io::Result<()> {
+        // move home, clear the rest of every line and everything below the frame
+        write!(stdout, "\x1b[H{}\x1b[J", frame.replace('\n', "\x1b[K\n"))?;
+        stdout.flush()?;
+        thread::sleep(frame_time);
+        Ok(())
+    });
+    // always restore the cursor
+    write!(stdout, "\x1b[?25h")?;
+    stdout.flush()?;
+    Ok(result?)
+}
+
+fn program(args: &Args) -> Result<day14::TiltProgram> {
+    let program = args.flag_value("--program").unwrap_or("NWSE");
+    program
+        .parse()
+        .map_err(|_| anyhow!("invalid tilt program, expected N, E, S and W: {program}"))
+}
+
+fn entry(args: &Args) -> Result<(Vec2i, Direction)> {
+    match args.flag_value("--entry") {
+        None => Ok((Vec2i::new(0, 0), Direction::East)),
+        Some(entry) => parse_entry(entry),
+    }
+}
+
+fn parse_entry(s: &str) -> Result<(Vec2i, Direction)> {
+    let invalid = || anyhow!("invalid entry beam, expected X,Y,DIR: {s}");
+    let [x, y, dir] = s.split(',').collect::<Vec<_>>()[..] else {
+        return Err(invalid());
+    };
+    let dir = dir
+        .chars()
+        .next()
+        .and_then(|c| Direction::try_from(c).ok())
+        .ok_or_else(invalid)?;
+    Ok((
+        Vec2i::new(
+            x.parse().map_err(|_| invalid())?,
+            y.parse().map_err(|_| invalid())?,
+        ),
+        dir,
+    ))
+}
+
+fn main() -> Result<()> {
+    let args: Vec<_> = env::args().skip(1).collect();
+    let (command, args) = args.split_first().ok_or_else(|| anyhow!(USAGE))?;
+    let args = Args::parse(args)?;
+    if command == "play" {
+        return play(&args);
+    }
+
+    let scale = args.parsed_flag_value("--scale", 4)?;
+    let output = match command.as_str() {
+        "dot" => dot(&args)?.into_bytes(),
+        "ppm" => image(&args)?.to_ppm(scale, |_, c| *c),
+        "svg" => image(&args)?.to_svg(scale, |_, c| *c).into_bytes(),
+        command => bail!("unknown command: {command}"),
+    };
+    io::stdout().write_all(&output)?;
+    Ok(())
+}
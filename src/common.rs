@@ -1,8 +1,10 @@
+use itertools::Itertools;
 use nalgebra::{Dim, Matrix, Matrix2, Scalar, Storage, Vector2};
 use nalgebra::{SVector, Vector3};
 use num::rational::Ratio;
-use num::{Rational64, Signed};
-use std::fmt::Debug;
+use num::{CheckedAdd, CheckedMul, CheckedSub, FromPrimitive, One, Rational64, Signed, Zero};
+use rustc_hash::FxHashMap;
+use std::fmt::{Debug, Display, Formatter, Write};
 use std::hash::Hash;
 use std::ops::{Index, IndexMut};
 use std::str::FromStr;
@@ -31,6 +33,73 @@ pub fn lp1_norm<T: Scalar + Signed, R: Dim, C: Dim, S: Storage<T, R, C>>(
     }
 }
 
+#[derive(Error, Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[error("arithmetic overflow")]
+pub struct OverflowError;
+
+/// Integer types the overflow-checked solvers can compute in.
+///
+/// Fixed-width primitives report overflow as [`OverflowError`], while the arbitrary-precision
+/// [`num::BigInt`] and [`num::BigUint`] never fail.
+pub trait CheckedInteger:
+    Clone
+    + Debug
+    + Display
+    + Ord
+    + FromStr
+    + Zero
+    + One
+    + CheckedAdd
+    + CheckedSub
+    + CheckedMul
+    + FromPrimitive
+{
+    fn try_add(&self, rhs: &Self) -> Result<Self, OverflowError> {
+        self.checked_add(rhs).ok_or(OverflowError)
+    }
+
+    fn try_sub(&self, rhs: &Self) -> Result<Self, OverflowError> {
+        self.checked_sub(rhs).ok_or(OverflowError)
+    }
+
+    fn try_mul(&self, rhs: &Self) -> Result<Self, OverflowError> {
+        self.checked_mul(rhs).ok_or(OverflowError)
+    }
+
+    fn try_from_i64(n: i64) -> Result<Self, OverflowError> {
+        Self::from_i64(n).ok_or(OverflowError)
+    }
+
+    fn try_from_usize(n: usize) -> Result<Self, OverflowError> {
+        Self::from_usize(n).ok_or(OverflowError)
+    }
+}
+
+impl<T> CheckedInteger for T where
+    T: Clone
+        + Debug
+        + Display
+        + Ord
+        + FromStr
+        + Zero
+        + One
+        + CheckedAdd
+        + CheckedSub
+        + CheckedMul
+        + FromPrimitive
+{
+}
+
+pub fn checked_sum<T: CheckedInteger>(it: impl IntoIterator<Item = T>) -> Result<T, OverflowError> {
+    it.into_iter().try_fold(T::zero(), |acc, x| acc.try_add(&x))
+}
+
+pub fn checked_product<T: CheckedInteger>(
+    it: impl IntoIterator<Item = T>,
+) -> Result<T, OverflowError> {
+    it.into_iter().try_fold(T::one(), |acc, x| acc.try_mul(&x))
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum Direction {
     North,
@@ -58,6 +127,16 @@ impl TryFrom<char> for Direction {
 impl Direction {
     pub const VALUES: [Self; 4] = [Self::North, Self::East, Self::South, Self::West];
 
+    /// Position of the direction in [`Direction::VALUES`].
+    pub fn index(&self) -> usize {
+        match self {
+            Self::North => 0,
+            Self::East => 1,
+            Self::South => 2,
+            Self::West => 3,
+        }
+    }
+
     pub fn opposite(&self) -> Self {
         match self {
             Self::North => Self::South,
@@ -103,6 +182,35 @@ impl Direction {
     }
 }
 
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    pub const BLACK: Self = Self(0x00, 0x00, 0x00);
+    pub const WHITE: Self = Self(0xff, 0xff, 0xff);
+
+    /// Parses a `0xRRGGBB` color.
+    pub fn from_hex(hex: u32) -> Self {
+        Self((hex >> 16) as u8, (hex >> 8) as u8, hex as u8)
+    }
+
+    /// Linear interpolation between `self` (`t = 0`) and `other` (`t = 1`).
+    pub fn mix(&self, other: &Self, t: f64) -> Self {
+        let lerp = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        Self(
+            lerp(self.0, other.0),
+            lerp(self.1, other.1),
+            lerp(self.2, other.2),
+        )
+    }
+}
+
+impl Display for Rgb {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Grid<T> {
     pub size_x: usize,
@@ -125,9 +233,10 @@ where
             .filter(|l| !l.is_empty())
             .flat_map(|l| {
                 size_y += 1;
+                let len = l.chars().count();
                 match size_x {
-                    None => size_x = Some(l.len()),
-                    Some(size_x) if size_x == l.len() => {}
+                    None => size_x = Some(len),
+                    Some(size_x) if size_x == len => {}
                     _ => {
                         panic!("non rectangular grid");
                     }
@@ -204,6 +313,78 @@ impl<T> Grid<T> {
             (pos, &self[pos])
         })
     }
+
+    pub fn map<U>(&self, mut f: impl FnMut(Vec2i, &T) -> U) -> Grid<U> {
+        Grid {
+            size_x: self.size_x,
+            size_y: self.size_y,
+            grid: self.pos_iter().map(|(pos, t)| f(pos, t)).collect(),
+        }
+    }
+
+    /// Renders a binary PPM image with `scale`×`scale` pixels per tile.
+    pub fn to_ppm(&self, scale: usize, color: impl Fn(Vec2i, &T) -> Rgb) -> Vec<u8> {
+        let colors = self.map(color);
+        let mut ppm =
+            format!("P6\n{} {}\n255\n", self.size_x * scale, self.size_y * scale).into_bytes();
+        for y in 0..self.size_y {
+            let row: Vec<_> = colors
+                .iter_row(y as i64)
+                .flat_map(|c| std::iter::repeat_n([c.0, c.1, c.2], scale))
+                .flatten()
+                .collect();
+            for _ in 0..scale {
+                ppm.extend_from_slice(&row);
+            }
+        }
+
+        ppm
+    }
+
+    /// Renders an SVG image with `scale`×`scale` pixels per tile.
+    pub fn to_svg(&self, scale: usize, color: impl Fn(Vec2i, &T) -> Rgb) -> String {
+        let colors = self.map(color);
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}" shape-rendering="crispEdges">"#,
+            self.size_x * scale,
+            self.size_y * scale,
+            self.size_x,
+            self.size_y
+        )
+        .unwrap();
+        for y in 0..self.size_y {
+            // merge runs of the same color into a single rectangle
+            let mut x = 0;
+            for (c, run) in &colors.iter_row(y as i64).chunk_by(|c| **c) {
+                let width = run.count();
+                writeln!(
+                    svg,
+                    r#"<rect x="{x}" y="{y}" width="{width}" height="1" fill="{c}"/>"#
+                )
+                .unwrap();
+                x += width;
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+impl<T: Copy> Display for Grid<T>
+where
+    char: From<T>,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for y in 0..self.size_y {
+            for t in self.iter_row(y as i64) {
+                f.write_char(char::from(*t))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
 }
 
 impl<T> Index<Vec2i> for Grid<T> {
@@ -220,6 +401,121 @@ impl<T> IndexMut<Vec2i> for Grid<T> {
     }
 }
 
+/// Maps names to dense `u32` ids, assigned in order of first appearance, and back.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Interner {
+    ids: FxHashMap<String, u32>,
+    names: Vec<String>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intern(&mut self, name: &str) -> u32 {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+
+        let id = self.names.len().try_into().expect("too many names");
+        self.ids.insert(name.to_string(), id);
+        self.names.push(name.to_string());
+        id
+    }
+
+    pub fn get(&self, name: &str) -> Option<u32> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: u32) -> &str {
+        &self.names[id as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &str)> {
+        self.names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| (id as u32, name.as_str()))
+    }
+}
+
+/// Minimal writer for Graphviz DOT graphs, rendered through [`Display`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dot {
+    directed: bool,
+    statements: Vec<String>,
+}
+
+impl Dot {
+    pub fn new(directed: bool) -> Self {
+        Self {
+            directed,
+            statements: vec![],
+        }
+    }
+
+    pub fn graph_attributes(&mut self, attributes: &[(&str, &str)]) -> &mut Self {
+        self.statements
+            .push(format!("graph{}", Self::attribute_list(attributes)));
+        self
+    }
+
+    pub fn node(&mut self, id: &str, attributes: &[(&str, &str)]) -> &mut Self {
+        self.statements.push(format!(
+            "{}{}",
+            Self::quote(id),
+            Self::attribute_list(attributes)
+        ));
+        self
+    }
+
+    pub fn edge(&mut self, from: &str, to: &str, attributes: &[(&str, &str)]) -> &mut Self {
+        self.statements.push(format!(
+            "{} {} {}{}",
+            Self::quote(from),
+            if self.directed { "->" } else { "--" },
+            Self::quote(to),
+            Self::attribute_list(attributes)
+        ));
+        self
+    }
+
+    fn quote(s: &str) -> String {
+        format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+
+    fn attribute_list(attributes: &[(&str, &str)]) -> String {
+        if attributes.is_empty() {
+            return String::new();
+        }
+
+        let attributes: Vec<_> = attributes
+            .iter()
+            .map(|(k, v)| format!("{k}={}", Self::quote(v)))
+            .collect();
+        format!(" [{}]", attributes.join(", "))
+    }
+}
+
+impl Display for Dot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} {{", if self.directed { "digraph" } else { "graph" })?;
+        for statement in &self.statements {
+            writeln!(f, "    {statement};")?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
 pub fn parse_split_whitespace<T: FromStr, B: FromIterator<T>>(
     s: &str,
 ) -> Result<B, <T as FromStr>::Err> {
@@ -278,3 +574,46 @@ pub fn parse_vec<T: Scalar + FromStr, const D: usize>(
 
     Ok(SVector::from_iterator(data.into_iter().flatten()))
 }
+
+/// Deterministic xorshift generator for randomized tests, always starting from the same seed.
+#[cfg(test)]
+#[derive(Debug, Clone)]
+pub struct XorShift(u64);
+
+#[cfg(test)]
+impl Default for XorShift {
+    fn default() -> Self {
+        XorShift(0x2545f4914f6cdd1d)
+    }
+}
+
+#[cfg(test)]
+impl XorShift {
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn bit(&mut self) -> bool {
+        self.next_u64() & 1 == 1
+    }
+
+    /// A grid with every tile chosen by `tile` from a random number below `n`.
+    pub fn grid<T>(
+        &mut self,
+        size_x: usize,
+        size_y: usize,
+        n: u64,
+        mut tile: impl FnMut(u64) -> T,
+    ) -> Grid<T> {
+        Grid {
+            size_x,
+            size_y,
+            grid: (0..size_x * size_y)
+                .map(|_| tile(self.next_u64() % n))
+                .collect(),
+        }
+    }
+}
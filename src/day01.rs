@@ -1,76 +1,148 @@
+use std::collections::VecDeque;
+
 use aoc_runner_derive::{aoc, aoc_generator};
 
-fn parse_digit(c: char) -> Option<u32> {
-    c.to_digit(10)
+/// A word of the vocabulary found by [`Scanner::scan`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Token {
+    /// byte offset of the first byte of the word
+    pub pos: usize,
+    /// length of the word in bytes
+    pub len: usize,
+    pub value: u32,
 }
 
-#[aoc_generator(day1, part1)]
-pub fn input_generator_1(input: &str) -> Vec<(u32, u32)> {
-    input
-        .lines()
-        .map(|l| {
-            (
-                l.chars().find_map(parse_digit).unwrap(),
-                l.chars().rev().find_map(parse_digit).unwrap(),
-            )
-        })
-        .collect()
+/// Aho-Corasick automaton finding all, possibly overlapping, occurrences of a vocabulary in a
+/// single pass.
+#[derive(Debug, Clone)]
+pub struct Scanner {
+    /// complete transition table over bytes, state 0 is the root
+    transitions: Vec<[u32; 256]>,
+    /// `(len, value)` of every word ending in a state, including those reached via failure links
+    outputs: Vec<Vec<(usize, u32)>>,
 }
 
-const NUMBERS: [&str; 9] = [
-    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
-];
-
-#[aoc_generator(day1, part2)]
-pub fn input_generator_2(input: &str) -> Vec<(u32, u32)> {
-    fn first_digit(s: &str) -> Option<u32> {
-        let mut first_digit: Option<(usize, u32)> = None;
-        for c in '0'..='9' {
-            if let Some(pos) = s.find(c) {
-                if first_digit.is_none() || first_digit.is_some_and(|(min_idx, _)| pos < min_idx) {
-                    first_digit = Some((pos, parse_digit(c).unwrap()));
+impl Scanner {
+    const MISSING: u32 = u32::MAX;
+
+    pub fn new<'a>(vocabulary: impl IntoIterator<Item = (&'a str, u32)>) -> Self {
+        let mut transitions = vec![[Self::MISSING; 256]];
+        let mut outputs = vec![vec![]];
+        for (word, value) in vocabulary {
+            assert!(!word.is_empty(), "assume: words are not empty");
+            let mut state = 0;
+            for b in word.bytes() {
+                if transitions[state][b as usize] == Self::MISSING {
+                    transitions[state][b as usize] = transitions.len() as u32;
+                    transitions.push([Self::MISSING; 256]);
+                    outputs.push(vec![]);
                 }
+                state = transitions[state][b as usize] as usize;
             }
+            // later words replace earlier duplicates
+            outputs[state] = vec![(word.len(), value)];
         }
 
-        for (idx, number) in NUMBERS.iter().enumerate() {
-            if let Some(pos) = s.find(number) {
-                if first_digit.is_none() || first_digit.is_some_and(|(min_idx, _)| pos < min_idx) {
-                    first_digit = Some((pos, (idx + 1) as u32));
+        // breadth-first, so the failure state of every node is complete before it is needed
+        let mut fail = vec![0; transitions.len()];
+        let mut q = VecDeque::from([0]);
+        while let Some(state) = q.pop_front() {
+            let fallbacks = if state == 0 {
+                [0; 256]
+            } else {
+                transitions[fail[state]]
+            };
+            for (next, fallback) in transitions[state].iter_mut().zip(fallbacks) {
+                if *next == Self::MISSING {
+                    *next = fallback;
+                } else {
+                    let next = *next as usize;
+                    fail[next] = fallback as usize;
+                    let inherited = outputs[fail[next]].clone();
+                    outputs[next].extend(inherited);
+                    q.push_back(next);
                 }
             }
         }
 
-        first_digit.map(|(_, digit)| digit)
+        Self {
+            transitions,
+            outputs,
+        }
     }
 
-    fn last_digit(s: &str) -> Option<u32> {
-        let mut last_digit: Option<(usize, u32)> = None;
-        for c in '0'..='9' {
-            if let Some(pos) = s.rfind(c) {
-                if last_digit.is_none() || last_digit.is_some_and(|(min_idx, _)| pos > min_idx) {
-                    last_digit = Some((pos, parse_digit(c).unwrap()));
-                }
-            }
-        }
+    /// The decimal digits `0` to `9`.
+    pub fn digits() -> Self {
+        Self::new(DIGITS.into_iter().zip(0..))
+    }
 
-        for (idx, number) in NUMBERS.iter().enumerate() {
-            if let Some(pos) = s.rfind(number) {
-                if last_digit.is_none() || last_digit.is_some_and(|(min_idx, _)| pos > min_idx) {
-                    last_digit = Some((pos, (idx + 1) as u32));
-                }
-            }
-        }
+    /// The decimal digits and the spelled out english words `one` to `nine`.
+    pub fn english() -> Self {
+        Self::new(
+            DIGITS
+                .into_iter()
+                .zip(0..)
+                .chain(NUMBERS.into_iter().zip(1..)),
+        )
+    }
 
-        last_digit.map(|(_, digit)| digit)
+    /// All tokens in `s`, ordered by their end position.
+    pub fn scan<'a>(&'a self, s: &'a str) -> impl Iterator<Item = Token> + 'a {
+        s.bytes()
+            .enumerate()
+            .scan(0, |state, (i, b)| {
+                *state = self.transitions[*state as usize][b as usize];
+                Some((i, *state))
+            })
+            .flat_map(move |(i, state)| {
+                self.outputs[state as usize]
+                    .iter()
+                    .map(move |&(len, value)| Token {
+                        pos: i + 1 - len,
+                        len,
+                        value,
+                    })
+            })
     }
 
+    /// Values of the first and the last token in `s`.
+    pub fn first_last(&self, s: &str) -> Option<(u32, u32)> {
+        let (first, last) = self.scan(s).fold(None, |acc: Option<(Token, Token)>, t| {
+            Some(match acc {
+                None => (t, t),
+                Some((first, last)) => (
+                    if t.pos < first.pos { t } else { first },
+                    if t.pos > last.pos { t } else { last },
+                ),
+            })
+        })?;
+        Some((first.value, last.value))
+    }
+}
+
+const DIGITS: [&str; 10] = ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
+
+const NUMBERS: [&str; 9] = [
+    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+
+fn calibration_values(input: &str, scanner: &Scanner) -> Vec<(u32, u32)> {
     input
         .lines()
-        .map(|l| (first_digit(l).unwrap(), last_digit(l).unwrap()))
+        .map(|l| scanner.first_last(l).unwrap())
         .collect()
 }
 
+#[aoc_generator(day1, part1)]
+pub fn input_generator_1(input: &str) -> Vec<(u32, u32)> {
+    calibration_values(input, &Scanner::digits())
+}
+
+#[aoc_generator(day1, part2)]
+pub fn input_generator_2(input: &str) -> Vec<(u32, u32)> {
+    calibration_values(input, &Scanner::english())
+}
+
 #[aoc(day1, part1)]
 pub fn part1(input: &[(u32, u32)]) -> u32 {
     input.iter().map(|&(first, last)| first * 10 + last).sum()
@@ -109,4 +181,49 @@ zoneight234
 
         assert_eq!(part2(&input_generator_2(INPUT)), 281);
     }
+
+    #[test]
+    fn test_scan_overlapping() {
+        let scanner = Scanner::english();
+        assert_eq!(
+            scanner.scan("eightwo3").collect::<Vec<_>>(),
+            [
+                Token {
+                    pos: 0,
+                    len: 5,
+                    value: 8
+                },
+                Token {
+                    pos: 4,
+                    len: 3,
+                    value: 2
+                },
+                Token {
+                    pos: 7,
+                    len: 1,
+                    value: 3
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_vocabulary() {
+        let scanner = Scanner::new([
+            ("zero", 0),
+            ("ten", 10),
+            ("eins", 1),
+            ("zehn", 10),
+            ("n", 99),
+        ]);
+        assert_eq!(
+            scanner
+                .scan("zeinszehnten")
+                .map(|t| (t.pos, t.value))
+                .collect::<Vec<_>>(),
+            [(3, 99), (1, 1), (5, 10), (8, 99), (9, 10), (11, 99)]
+        );
+        assert_eq!(scanner.first_last("xtenzero"), Some((10, 0)));
+        assert_eq!(scanner.first_last("abc"), None);
+    }
 }
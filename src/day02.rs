@@ -1,40 +1,98 @@
+use std::collections::BTreeMap;
 use std::str::FromStr;
 
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
 
-use crate::common::{parse_lines, parse_split};
+use crate::common::{checked_product, checked_sum, parse_lines, parse_split, OverflowError};
 
-#[derive(Debug, Default)]
+fn parse_counts(s: &str) -> Result<BTreeMap<String, u32>, ()> {
+    s.split(',').try_fold(BTreeMap::new(), |mut a, e| {
+        let Some((n, color)) = e.split_whitespace().collect_tuple() else {
+            return Err(());
+        };
+
+        let n: u32 = n.parse().map_err(|_| ())?;
+        *a.entry(color.to_string()).or_default() += n;
+        Ok(a)
+    })
+}
+
+/// Cubes revealed at once, by color.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
 pub struct Draw {
-    red: u32,
-    green: u32,
-    blue: u32,
+    counts: BTreeMap<String, u32>,
+}
+
+impl Draw {
+    pub fn get(&self, color: &str) -> u32 {
+        self.counts.get(color).copied().unwrap_or_default()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.counts.iter().map(|(c, n)| (c.as_str(), *n))
+    }
 }
 
 impl FromStr for Draw {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        s.split(',')
-            .try_fold(Draw::default(), |mut a, e| {
-                let Some((n, color)) = e.split_whitespace().collect_tuple() else {
-                    return Err(());
-                };
+        Ok(Self {
+            counts: parse_counts(s)?,
+        })
+    }
+}
+
+/// Contents of the bag, by color. Colors that are not mentioned have no cubes.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct Bag {
+    counts: BTreeMap<String, u32>,
+}
+
+impl Bag {
+    pub fn new<'a>(counts: impl IntoIterator<Item = (&'a str, u32)>) -> Self {
+        Self {
+            counts: counts
+                .into_iter()
+                .map(|(c, n)| (c.to_string(), n))
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, color: &str) -> u32 {
+        self.counts.get(color).copied().unwrap_or_default()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.counts.iter().map(|(c, n)| (c.as_str(), *n))
+    }
+
+    pub fn can_draw(&self, draw: &Draw) -> bool {
+        draw.iter().all(|(c, n)| n <= self.get(c))
+    }
+
+    /// Whether every cube of `other` also fits into this bag.
+    pub fn contains(&self, other: &Bag) -> bool {
+        other.iter().all(|(c, n)| n <= self.get(c))
+    }
+
+    /// Product of the number of cubes of the given colors.
+    pub fn power<'a>(
+        &self,
+        colors: impl IntoIterator<Item = &'a str>,
+    ) -> Result<u32, OverflowError> {
+        checked_product(colors.into_iter().map(|c| self.get(c)))
+    }
+}
 
-                let n: u32 = n.parse().map_err(|_| ())?;
-                match color {
-                    "red" => a.red += n,
-                    "green" => a.green += n,
-                    "blue" => a.blue += n,
-                    _ => {
-                        return Err(());
-                    }
-                }
+impl FromStr for Bag {
+    type Err = ();
 
-                Ok(a)
-            })
-            .map_err(|_| ())
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self {
+            counts: parse_counts(s)?,
+        })
     }
 }
 
@@ -44,6 +102,30 @@ pub struct Game {
     draws: Vec<Draw>,
 }
 
+impl Game {
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn draws(&self) -> &[Draw] {
+        &self.draws
+    }
+
+    pub fn is_feasible(&self, bag: &Bag) -> bool {
+        self.draws.iter().all(|d| bag.can_draw(d))
+    }
+
+    /// The smallest bag that makes this game feasible.
+    pub fn minimal_bag(&self) -> Bag {
+        let mut bag = Bag::default();
+        for (c, n) in self.draws.iter().flat_map(Draw::iter) {
+            let max = bag.counts.entry(c.to_string()).or_default();
+            *max = n.max(*max);
+        }
+        bag
+    }
+}
+
 impl FromStr for Game {
     type Err = ();
 
@@ -62,6 +144,25 @@ impl FromStr for Game {
     }
 }
 
+pub fn feasible_games<'a>(games: &'a [Game], bag: &'a Bag) -> impl Iterator<Item = &'a Game> {
+    games.iter().filter(|g| g.is_feasible(bag))
+}
+
+/// Ids of the feasible games for each of the candidate bags. The minimal bag of every game is
+/// only computed once.
+pub fn feasible_games_per_bag(games: &[Game], bags: &[Bag]) -> Vec<Vec<u32>> {
+    let minimal_bags: Vec<_> = games.iter().map(|g| (g.id, g.minimal_bag())).collect();
+    bags.iter()
+        .map(|bag| {
+            minimal_bags
+                .iter()
+                .filter(|(_, minimal)| bag.contains(minimal))
+                .map(|(id, _)| *id)
+                .collect()
+        })
+        .collect()
+}
+
 #[aoc_generator(day2)]
 pub fn input_generator(input: &str) -> Vec<Game> {
     parse_lines(input).unwrap()
@@ -69,28 +170,16 @@ pub fn input_generator(input: &str) -> Vec<Game> {
 
 #[aoc(day2, part1)]
 pub fn part1(input: &[Game]) -> u32 {
-    input
-        .iter()
-        .filter(|g| {
-            g.draws
-                .iter()
-                .all(|d| d.red <= 12 && d.green <= 13 && d.blue <= 14)
-        })
-        .map(|g| g.id)
-        .sum()
+    let bag = Bag::new([("red", 12), ("green", 13), ("blue", 14)]);
+    feasible_games(input, &bag).map(|g| g.id).sum()
 }
 
 #[aoc(day2, part2)]
-pub fn part2(input: &[Game]) -> u32 {
+pub fn part2(input: &[Game]) -> Result<u32, OverflowError> {
     input
         .iter()
-        .map(|g| {
-            let red = g.draws.iter().map(|d| d.red).max().unwrap_or_default();
-            let green = g.draws.iter().map(|d| d.green).max().unwrap_or_default();
-            let blue = g.draws.iter().map(|d| d.blue).max().unwrap_or_default();
-            red * green * blue
-        })
-        .sum()
+        .map(|g| g.minimal_bag().power(["red", "green", "blue"]))
+        .process_results(|it| checked_sum(it))?
 }
 
 #[cfg(test)]
@@ -112,6 +201,55 @@ Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green"#;
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&input_generator(INPUT)), 2286);
+        assert_eq!(part2(&input_generator(INPUT)), Ok(2286));
+    }
+
+    #[test]
+    fn test_minimal_bag() {
+        let games = input_generator(INPUT);
+        assert_eq!(
+            games[0].minimal_bag(),
+            "4 red, 2 green, 6 blue".parse().unwrap()
+        );
+        assert!(games[0].is_feasible(&games[0].minimal_bag()));
+    }
+
+    #[test]
+    fn test_feasible_games_per_bag() {
+        let games = input_generator(INPUT);
+        let bags = [
+            Bag::new([("red", 12), ("green", 13), ("blue", 14)]),
+            Bag::new([("red", 20), ("green", 13), ("blue", 15)]),
+            Bag::new([("red", 6), ("green", 3), ("blue", 6), ("yellow", 1)]),
+            Bag::default(),
+        ];
+        assert_eq!(
+            feasible_games_per_bag(&games, &bags),
+            [vec![1, 2, 5], vec![1, 2, 3, 4, 5], vec![1, 2, 5], vec![]]
+        );
+    }
+
+    #[test]
+    fn test_other_colors() {
+        let games = input_generator("Game 7: 2 yellow, 1 red; 3 yellow\nGame 8: 1 purple");
+        let bag: Bag = "3 yellow, 1 red".parse().unwrap();
+        assert_eq!(
+            feasible_games(&games, &bag)
+                .map(Game::id)
+                .collect::<Vec<_>>(),
+            [7]
+        );
+        assert_eq!(games[0].minimal_bag().power(["yellow", "red"]), Ok(3));
+    }
+
+    #[test]
+    fn test_power_overflow() {
+        let bag: Bag = "70000 red, 70000 green".parse().unwrap();
+        assert_eq!(bag.power(["red"]), Ok(70000));
+        assert_eq!(bag.power(["red", "green"]), Err(OverflowError));
+        assert_eq!(
+            part2(&input_generator("Game 1: 70000 red, 70000 green, 1 blue")),
+            Err(OverflowError)
+        );
     }
 }
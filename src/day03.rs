@@ -1,117 +1,178 @@
+use std::num::ParseIntError;
+
 use aoc_runner_derive::{aoc, aoc_generator};
-use regex::Regex;
-use rustc_hash::FxHashMap;
+use itertools::Itertools;
 
-use crate::common::parse_lines;
+use crate::common::{checked_product, checked_sum, Grid, OverflowError, Vec2i};
 
-#[aoc_generator(day3)]
-pub fn input_generator(input: &str) -> Vec<String> {
-    parse_lines(input).unwrap()
+/// A horizontal run of digits.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Number {
+    pub value: u32,
+    /// position of the leftmost digit
+    pub pos: Vec2i,
+    pub len: usize,
 }
 
-#[aoc(day3, part1)]
-pub fn part1(input: &[String]) -> u32 {
-    fn contains_symbol(s: &str) -> bool {
-        s.chars().any(|c| c != '.' && !c.is_ascii_digit())
-    }
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Symbol {
+    pub symbol: char,
+    pub pos: Vec2i,
+}
 
-    let re = Regex::new(r#"\d+"#).unwrap();
+/// Gears are symbols of the given kind with exactly `adjacent` adjacent numbers, their ratio is the
+/// product of those numbers.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct GearRule {
+    pub symbol: char,
+    pub adjacent: usize,
+}
 
-    let mut sum = 0;
-    for (i, l) in input.iter().enumerate() {
-        for m in re.find_iter(l) {
-            let start = m.start();
-            let end = m.end();
+impl Default for GearRule {
+    fn default() -> Self {
+        Self {
+            symbol: '*',
+            adjacent: 2,
+        }
+    }
+}
 
-            // assume rectangular grid and ascii
-            let startm1 = if start > 0 { start - 1 } else { start };
-            let endp1 = if end < l.len() - 1 { end + 1 } else { end };
+#[derive(Debug, Clone)]
+pub struct Schematic {
+    grid: Grid<char>,
+    numbers: Vec<Number>,
+    symbols: Vec<Symbol>,
+    /// indices of the adjacent symbols, indexed like `numbers`
+    number_symbols: Vec<Vec<usize>>,
+    /// indices of the adjacent numbers, indexed like `symbols`
+    symbol_numbers: Vec<Vec<usize>>,
+}
 
-            let has_symbol_fn = || {
-                if i > 0 {
-                    let prev = &input[i - 1][startm1..endp1];
-                    if contains_symbol(prev) {
-                        return true;
-                    }
+impl Schematic {
+    /// Fails when a number doesn't fit into a `u32`.
+    pub fn new(grid: Grid<char>) -> Result<Self, ParseIntError> {
+        let mut numbers = vec![];
+        let mut number_ids = grid.map(|_, _| None);
+        for y in 0..grid.size_y as i64 {
+            for (is_digit, run) in &grid.pos_iter_row(y).chunk_by(|(_, c)| c.is_ascii_digit()) {
+                if !is_digit {
+                    continue;
                 }
 
-                if i < input.len() - 1 {
-                    let next = &input[i + 1][startm1..endp1];
-                    if contains_symbol(next) {
-                        return true;
-                    }
+                let run: Vec<_> = run.collect();
+                for (pos, _) in &run {
+                    number_ids[*pos] = Some(numbers.len());
                 }
-
-                contains_symbol(&l[startm1..(startm1 + 1)])
-                    || contains_symbol(&l[(endp1 - 1)..endp1])
-            };
-
-            if has_symbol_fn() {
-                let n: u32 = m.as_str().parse().unwrap();
-                sum += n;
+                numbers.push(Number {
+                    value: run.iter().map(|(_, c)| *c).collect::<String>().parse()?,
+                    pos: run[0].0,
+                    len: run.len(),
+                });
             }
         }
-    }
 
-    sum
-}
+        let symbols: Vec<_> = grid
+            .pos_iter()
+            .filter(|(_, c)| **c != '.' && !c.is_ascii_digit())
+            .map(|(pos, c)| Symbol { symbol: *c, pos })
+            .collect();
 
-#[aoc(day3, part2)]
-pub fn part2(input: &[String]) -> u32 {
-    fn find_star(s: &str) -> Option<usize> {
-        s.chars().position(|c| c == '*')
-    }
+        let mut number_symbols = vec![vec![]; numbers.len()];
+        let symbol_numbers: Vec<Vec<usize>> = symbols
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                let adjacent: Vec<_> = (-1..=1)
+                    .cartesian_product(-1..=1)
+                    .map(|(dy, dx)| s.pos + Vec2i::new(dx, dy))
+                    .filter(|pos| grid.in_bounds(pos))
+                    .filter_map(|pos| number_ids[pos])
+                    .unique()
+                    .collect();
+                for &n in &adjacent {
+                    number_symbols[n].push(i);
+                }
+                adjacent
+            })
+            .collect();
 
-    let re = Regex::new(r#"\d+"#).unwrap();
+        Ok(Self {
+            grid,
+            numbers,
+            symbols,
+            number_symbols,
+            symbol_numbers,
+        })
+    }
 
-    let mut gears: FxHashMap<(usize, usize), Vec<u32>> = FxHashMap::default();
-    for (i, l) in input.iter().enumerate() {
-        for m in re.find_iter(l) {
-            let start = m.start();
-            let end = m.end();
+    pub fn grid(&self) -> &Grid<char> {
+        &self.grid
+    }
 
-            // assume rectangular grid and ascii
-            let startm1 = if start > 0 { start - 1 } else { start };
-            let endp1 = if end < l.len() - 1 { end + 1 } else { end };
+    pub fn numbers(&self) -> &[Number] {
+        &self.numbers
+    }
 
-            let has_symbol_fn = || {
-                if i > 0 {
-                    let prev = &input[i - 1][startm1..endp1];
-                    if let Some(pos) = find_star(prev) {
-                        return Some((i - 1, startm1 + pos));
-                    }
-                }
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
 
-                if i < input.len() - 1 {
-                    let next = &input[i + 1][startm1..endp1];
-                    if let Some(pos) = find_star(next) {
-                        return Some((i + 1, startm1 + pos));
-                    }
-                }
+    /// Symbols adjacent to the number with the given index.
+    pub fn adjacent_symbols(&self, number: usize) -> impl Iterator<Item = &Symbol> {
+        self.number_symbols[number]
+            .iter()
+            .map(|&s| &self.symbols[s])
+    }
 
-                if let Some(pos) = find_star(&l[startm1..(startm1 + 1)]) {
-                    return Some((i, startm1 + pos));
-                }
+    /// Numbers adjacent to the symbol with the given index.
+    pub fn adjacent_numbers(&self, symbol: usize) -> impl Iterator<Item = &Number> {
+        self.symbol_numbers[symbol]
+            .iter()
+            .map(|&n| &self.numbers[n])
+    }
 
-                if let Some(pos) = find_star(&l[(endp1 - 1)..endp1]) {
-                    return Some((i, endp1 - 1 + pos));
-                }
+    /// Numbers adjacent to at least one symbol.
+    pub fn part_numbers(&self) -> impl Iterator<Item = &Number> {
+        self.numbers
+            .iter()
+            .zip(&self.number_symbols)
+            .filter(|(_, symbols)| !symbols.is_empty())
+            .map(|(n, _)| n)
+    }
 
-                None
-            };
+    /// Indices of the symbols with exactly `n` adjacent numbers.
+    pub fn symbols_with_adjacent(&self, n: usize) -> impl Iterator<Item = usize> + '_ {
+        self.symbol_numbers
+            .iter()
+            .positions(move |numbers| numbers.len() == n)
+    }
 
-            if let Some(gear_pos) = has_symbol_fn() {
-                let n: u32 = m.as_str().parse().unwrap();
-                gears.entry(gear_pos).or_default().push(n);
-            }
-        }
+    /// Ratios of all gears according to the given rule.
+    pub fn gear_ratios(
+        &self,
+        rule: GearRule,
+    ) -> impl Iterator<Item = Result<u64, OverflowError>> + '_ {
+        self.symbols_with_adjacent(rule.adjacent)
+            .filter(move |&s| self.symbols[s].symbol == rule.symbol)
+            .map(|s| checked_product(self.adjacent_numbers(s).map(|n| n.value as u64)))
     }
+}
 
-    gears
-        .values()
-        .filter(|v| v.len() == 2)
-        .map(|v| v[0] * v[1])
-        .sum()
+#[aoc_generator(day3)]
+pub fn input_generator(input: &str) -> Schematic {
+    Schematic::new(input.parse().unwrap()).unwrap()
+}
+
+#[aoc(day3, part1)]
+pub fn part1(input: &Schematic) -> Result<u32, OverflowError> {
+    checked_sum(input.part_numbers().map(|n| n.value))
+}
+
+#[aoc(day3, part2)]
+pub fn part2(input: &Schematic) -> Result<u64, OverflowError> {
+    input
+        .gear_ratios(GearRule::default())
+        .process_results(|it| checked_sum(it))?
 }
 
 #[cfg(test)]
@@ -133,11 +194,72 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&input_generator(INPUT)), 4361);
+        assert_eq!(part1(&input_generator(INPUT)), Ok(4361));
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&input_generator(INPUT)), 467835);
+        assert_eq!(part2(&input_generator(INPUT)), Ok(467835));
+    }
+
+    #[test]
+    fn test_number_touching_two_gears() {
+        let schematic = input_generator(
+            r#"2*3
+.4.
+.*5"#,
+        );
+        let middle = schematic
+            .numbers()
+            .iter()
+            .position(|n| n.value == 4)
+            .unwrap();
+        assert_eq!(schematic.adjacent_symbols(middle).count(), 2);
+        assert_eq!(schematic.symbols_with_adjacent(3).collect::<Vec<_>>(), [0]);
+        assert_eq!(
+            schematic
+                .gear_ratios(GearRule {
+                    symbol: '*',
+                    adjacent: 3
+                })
+                .collect::<Vec<_>>(),
+            [Ok(24)]
+        );
+        assert_eq!(part2(&schematic), Ok(20));
+    }
+
+    #[test]
+    fn test_other_symbols() {
+        let schematic = input_generator(INPUT);
+        assert_eq!(
+            schematic
+                .gear_ratios(GearRule {
+                    symbol: '#',
+                    adjacent: 1
+                })
+                .collect::<Vec<_>>(),
+            [Ok(633)]
+        );
+        assert_eq!(schematic.symbols_with_adjacent(0).count(), 0);
+    }
+
+    #[test]
+    fn test_non_ascii() {
+        let schematic = input_generator("12·\n.€3");
+        assert_eq!(
+            schematic.part_numbers().map(|n| n.value).collect::<Vec<_>>(),
+            [12, 3]
+        );
+        assert_eq!(part1(&schematic), Ok(15));
+    }
+
+    #[test]
+    fn test_overflow() {
+        let grid = "99999999999*".parse().unwrap();
+        assert!(Schematic::new(grid).is_err());
+
+        let schematic = input_generator("4000000000*4000000000*4000000000");
+        assert_eq!(part1(&schematic), Err(OverflowError));
+        assert_eq!(part2(&schematic), Err(OverflowError));
     }
 }
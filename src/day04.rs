@@ -2,15 +2,60 @@ use std::str::FromStr;
 
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
+use num::BigUint;
 use rustc_hash::FxHashSet;
+use thiserror::Error;
 
-use crate::common::{parse_lines, parse_split_whitespace};
+use crate::common::{
+    checked_sum, parse_lines, parse_split_whitespace, CheckedInteger, OverflowError,
+};
+
+/// Set of card numbers, stored as a bitset if all of them are below 128.
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Numbers {
+    Bits(u128),
+    Set(FxHashSet<u32>),
+}
+
+impl Numbers {
+    fn contains(&self, n: u32) -> bool {
+        match self {
+            Self::Bits(bits) => n < u128::BITS && bits & (1 << n) != 0,
+            Self::Set(set) => set.contains(&n),
+        }
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = u32> + '_> {
+        match self {
+            Self::Bits(bits) => Box::new((0..u128::BITS).filter(move |n| bits & (1 << n) != 0)),
+            Self::Set(set) => Box::new(set.iter().copied()),
+        }
+    }
+
+    fn intersection_count(&self, other: &Self) -> usize {
+        match (self, other) {
+            (Self::Bits(a), Self::Bits(b)) => (a & b).count_ones() as usize,
+            _ => self.iter().filter(|n| other.contains(*n)).count(),
+        }
+    }
+}
+
+impl FromIterator<u32> for Numbers {
+    fn from_iter<I: IntoIterator<Item = u32>>(iter: I) -> Self {
+        let numbers: Vec<_> = iter.into_iter().collect();
+        if numbers.iter().all(|&n| n < u128::BITS) {
+            Self::Bits(numbers.iter().fold(0, |bits, n| bits | (1 << n)))
+        } else {
+            Self::Set(numbers.into_iter().collect())
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct Card {
-    _id: u32,
-    winning_numbers: FxHashSet<u32>,
-    my_numbers: FxHashSet<u32>,
+    id: u32,
+    winning_numbers: Numbers,
+    my_numbers: Numbers,
 }
 
 impl FromStr for Card {
@@ -29,7 +74,7 @@ impl FromStr for Card {
         };
 
         Ok(Card {
-            _id: id.trim().parse().map_err(|_| ())?,
+            id: id.trim().parse().map_err(|_| ())?,
             winning_numbers: parse_split_whitespace(winning_numbers).map_err(|_| ())?,
             my_numbers: parse_split_whitespace(my_numbers).map_err(|_| ())?,
         })
@@ -41,37 +86,103 @@ pub fn input_generator(input: &str) -> Vec<Card> {
     parse_lines(input).unwrap()
 }
 
-#[aoc(day4, part1)]
-pub fn part1(input: &[Card]) -> u32 {
-    input
-        .iter()
-        .map(|c| {
-            let n = c.winning_numbers.intersection(&c.my_numbers).count() as u32;
-            if n == 0 {
-                0
-            } else {
-                1 << (n - 1)
-            }
-        })
-        .sum()
+impl Card {
+    pub fn win_amount(&self) -> usize {
+        self.winning_numbers.intersection_count(&self.my_numbers)
+    }
 }
 
-#[aoc(day4, part2)]
-pub fn part2(input: &[Card]) -> u32 {
+/// What happens to copies won past the last card.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum OverrunPolicy {
+    /// the copies are dropped
+    #[default]
+    Clamp,
+    /// the copies continue at the first card, they are counted but don't win further copies
+    Wrap,
+    /// fail with [`CopyError::Overrun`]
+    Error,
+}
+
+#[derive(Error, Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CopyError {
+    /// holds the id of the card
+    #[error("card {0} wins copies past the last card")]
+    Overrun(u32),
+    #[error(transparent)]
+    Overflow(#[from] OverflowError),
+}
+
+fn total_points<T: CheckedInteger>(input: &[Card]) -> Result<T, OverflowError> {
+    let two = T::one().try_add(&T::one())?;
+    input.iter().try_fold(T::zero(), |sum, c| {
+        let points = match c.win_amount() {
+            0 => T::zero(),
+            n => num::checked_pow(two.clone(), n - 1).ok_or(OverflowError)?,
+        };
+        sum.try_add(&points)
+    })
+}
+
+/// Number of instances of every card, including the original.
+pub fn card_copies<T: CheckedInteger>(
+    input: &[Card],
+    policy: OverrunPolicy,
+) -> Result<Vec<T>, CopyError> {
     let l = input.len();
-    let mut counters = vec![1; l];
+    let mut counters = vec![T::one(); l];
+    // copies won past the last card, only added at the end so they don't win anything
+    let mut wrapped = vec![T::zero(); l];
     for i in 0..l {
-        let amount = counters[i];
-        let c = &input[i];
-        let win_amount = c.winning_numbers.intersection(&c.my_numbers).count();
+        let amount = counters[i].clone();
+        let win_amount = input[i].win_amount();
+        let end = i + 1 + win_amount;
+        if end > l {
+            match policy {
+                OverrunPolicy::Clamp => {}
+                OverrunPolicy::Wrap => {
+                    for j in l..end {
+                        let c = &mut wrapped[j % l];
+                        *c = c.try_add(&amount)?;
+                    }
+                }
+                OverrunPolicy::Error => return Err(CopyError::Overrun(input[i].id)),
+            }
+        }
 
-        // assume there won't be an out of bounds error
-        for c in &mut counters[(i + 1)..=(i + win_amount)] {
-            *c += amount;
+        for c in &mut counters[(i + 1)..end.min(l)] {
+            *c = c.try_add(&amount)?;
         }
     }
 
-    counters.iter().sum()
+    for (c, w) in counters.iter_mut().zip(&wrapped) {
+        *c = c.try_add(w)?;
+    }
+    Ok(counters)
+}
+
+fn total_cards<T: CheckedInteger>(input: &[Card]) -> Result<T, CopyError> {
+    Ok(checked_sum(card_copies(input, OverrunPolicy::Clamp)?)?)
+}
+
+#[aoc(day4, part1)]
+pub fn part1(input: &[Card]) -> Result<u32, OverflowError> {
+    total_points(input)
+}
+
+#[aoc(day4, part1, bigint)]
+pub fn part1_bigint(input: &[Card]) -> Result<BigUint, OverflowError> {
+    total_points(input)
+}
+
+#[aoc(day4, part2)]
+pub fn part2(input: &[Card]) -> Result<u32, CopyError> {
+    total_cards(input)
+}
+
+#[aoc(day4, part2, bigint)]
+pub fn part2_bigint(input: &[Card]) -> Result<BigUint, CopyError> {
+    total_cards(input)
 }
 
 #[cfg(test)]
@@ -89,11 +200,69 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11"#;
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&input_generator(INPUT)), 13);
+        assert_eq!(part1(&input_generator(INPUT)), Ok(13));
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&input_generator(INPUT)), 30);
+        assert_eq!(part2(&input_generator(INPUT)), Ok(30));
+    }
+
+    #[test]
+    fn test_part1_overflow() {
+        let numbers = (1..=40).join(" ");
+        let input = input_generator(&format!("Card 1: {numbers} | {numbers}"));
+        assert_eq!(part1(&input), Err(OverflowError));
+        assert_eq!(part1_bigint(&input), Ok(BigUint::from(1u64 << 39)));
+    }
+
+    #[test]
+    fn test_card_copies() {
+        assert_eq!(
+            card_copies::<u32>(&input_generator(INPUT), OverrunPolicy::Error),
+            Ok(vec![1, 2, 4, 8, 14, 1])
+        );
+    }
+
+    #[test]
+    fn test_overrun() {
+        let input = input_generator(
+            r#"Card 1: 1 | 2
+Card 2: 1 2 | 1 2
+Card 3: 1 200 | 1 200"#,
+        );
+        assert_eq!(
+            card_copies::<u32>(&input, OverrunPolicy::Clamp),
+            Ok(vec![1, 1, 2])
+        );
+        assert_eq!(
+            card_copies::<u32>(&input, OverrunPolicy::Wrap),
+            Ok(vec![4, 3, 2])
+        );
+        assert_eq!(
+            card_copies::<u32>(&input, OverrunPolicy::Error),
+            Err(CopyError::Overrun(2))
+        );
+
+        // the first card wins more copies than there are cards
+        let input = input_generator(
+            r#"Card 1: 1 2 3 4 5 | 1 2 3 4 5
+Card 2: 1 | 1
+Card 3: 1 | 2"#,
+        );
+        assert_eq!(
+            card_copies::<u32>(&input, OverrunPolicy::Wrap),
+            Ok(vec![2, 3, 5])
+        );
+    }
+
+    #[test]
+    fn test_numbers() {
+        let small: Numbers = [1, 5, 127].into_iter().collect();
+        let large: Numbers = [5, 127, 128].into_iter().collect();
+        assert_eq!(small, Numbers::Bits((1 << 1) | (1 << 5) | (1 << 127)));
+        assert!(matches!(large, Numbers::Set(_)));
+        assert_eq!(small.intersection_count(&large), 2);
+        assert_eq!(large.intersection_count(&small), 2);
     }
 }
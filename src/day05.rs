@@ -2,14 +2,26 @@ use std::str::FromStr;
 
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
+use num::BigUint;
+use thiserror::Error;
+
+use crate::common::{CheckedInteger, OverflowError};
+
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
+pub enum RouteError {
+    #[error("no maps lead from {0} to {1}")]
+    NoRoute(String, String),
+    #[error(transparent)]
+    Overflow(#[from] OverflowError),
+}
 
 #[derive(Debug)]
-pub struct Almanac {
-    initial: Vec<u32>,
-    maps: Vec<Map>,
+pub struct Almanac<T = u32> {
+    initial: Vec<T>,
+    maps: Vec<Map<T>>,
 }
 
-impl FromStr for Almanac {
+impl<T: CheckedInteger> FromStr for Almanac<T> {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -32,108 +44,297 @@ impl FromStr for Almanac {
     }
 }
 
-impl Almanac {
-    fn convert(&self, input: u32) -> u32 {
-        let mut n = input;
-        for m in &self.maps {
-            n = m.convert(n);
+impl<T: CheckedInteger> Almanac<T> {
+    /// Composes the maps along the category headers, e.g. from `"soil"` to `"humidity"`.
+    pub fn route(&self, from: &str, to: &str) -> Result<Map<T>, RouteError> {
+        let mut result = Map::identity(from);
+        // every map is used at most once, more steps would mean a cycle
+        for _ in 0..=self.maps.len() {
+            if result.destination == to {
+                return Ok(result);
+            }
+
+            let Some(next) = self.maps.iter().find(|m| m.source == result.destination) else {
+                break;
+            };
+            result = result.compose(next)?;
         }
 
-        n
+        Err(RouteError::NoRoute(from.to_string(), to.to_string()))
     }
 
-    fn convert_multi(&self, ranges: Vec<(u32, u32)>) -> Vec<(u32, u32)> {
-        let mut cur_ranges = ranges;
-        for m in &self.maps {
-            cur_ranges = m.convert_multi(cur_ranges);
-        }
-
-        cur_ranges
+    pub fn seed_to_location(&self) -> Result<Map<T>, RouteError> {
+        self.route("seed", "location")
     }
 }
 
-#[derive(Debug)]
-pub struct Map {
-    entries: Vec<MapEntry>,
+/// Piecewise linear map of the non-negative integers onto themselves, given by segments with
+/// strictly increasing starts. Each segment reaches up to the start of the next one, the last
+/// one is unbounded. Adjacent segments never continue each other linearly.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Map<T> {
+    source: String,
+    destination: String,
+    segments: Vec<Segment<T>>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct Segment<T> {
+    start: T,
+    destination: T,
 }
 
-impl FromStr for Map {
+impl<T: CheckedInteger> FromStr for Map<T> {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let entries = s
-            .trim()
-            .lines()
-            .skip(1)
+        let mut lines = s.trim().lines();
+        let (source, destination) = lines
+            .next()
+            .and_then(|l| l.strip_suffix(" map:"))
+            .and_then(|l| l.split_once("-to-"))
+            .ok_or(())?;
+        let entries = lines
             .map(str::parse)
             .collect::<Result<_, _>>()
             .map_err(|_| ())?;
 
-        Ok(Map { entries })
+        Self::from_entries(source, destination, entries)
     }
 }
 
-impl Map {
-    fn convert(&self, input: u32) -> u32 {
-        self.entries
-            .iter()
-            .filter_map(|e| e.convert(input))
-            .next()
-            .unwrap_or(input)
+impl<T: CheckedInteger> Map<T> {
+    pub fn identity(category: &str) -> Self {
+        Map {
+            source: category.to_string(),
+            destination: category.to_string(),
+            segments: vec![Segment {
+                start: T::zero(),
+                destination: T::zero(),
+            }],
+        }
     }
 
-    fn convert_multi(&self, mut input: Vec<(u32, u32)>) -> Vec<(u32, u32)> {
-        // this can be improved by merging interval results when they overlap/touch
-        // => similar to that cuboid puzzle (AoC 2021 day 22)
-        let mut results = Vec::new();
-        'outer: while let Some((start, len)) = input.pop() {
-            if len == 0 {
+    fn from_entries(
+        source: &str,
+        destination: &str,
+        mut entries: Vec<MapEntry<T>>,
+    ) -> Result<Self, ()> {
+        entries.sort_unstable_by(|a, b| a.source_start.cmp(&b.source_start));
+
+        let mut segments = vec![];
+        // `None` once an entry reaches past the largest representable number
+        let mut current = Some(T::zero());
+        for e in entries {
+            if e.range_length.is_zero() {
                 continue;
             }
 
-            let end = start + len;
-            for e in &self.entries {
-                let source_end = e.source_start + e.range_length;
-                // check for intersection (like in AoC 2022 day 4)
-                if start < source_end && end > e.source_start {
-                    // cutoff at the beginning of our interval
-                    if start < e.source_start {
-                        let start_cutoff = e.source_start - start;
-                        input.push((start, start_cutoff));
-                    }
-
-                    // cutoff at the end of our interval
-                    if end > source_end {
-                        let end_cutoff = end - source_end;
-                        input.push((source_end, end_cutoff));
-                    }
-
-                    // this is just the overlapping part in the middle
-                    let overlap_start = start.max(e.source_start);
-                    let overlap_end = end.min(source_end);
-                    let overlap_len = overlap_end - overlap_start;
-                    let offset = overlap_start - e.source_start;
-                    results.push((e.destination_start + offset, overlap_len));
-                    continue 'outer;
+            let Some(c) = current else {
+                return Err(());
+            };
+            if e.source_start < c {
+                // assume: entries don't overlap
+                return Err(());
+            }
+            if c < e.source_start {
+                segments.push(Segment {
+                    start: c.clone(),
+                    destination: c,
+                });
+            }
+
+            current = e.source_start.try_add(&e.range_length).ok();
+            segments.push(Segment {
+                start: e.source_start,
+                destination: e.destination_start,
+            });
+        }
+
+        if let Some(c) = current {
+            segments.push(Segment {
+                start: c.clone(),
+                destination: c,
+            });
+        }
+
+        Ok(Map {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            segments: merge(segments),
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    /// Number of linear pieces.
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    fn segment_index(&self, input: &T) -> usize {
+        self.segments.partition_point(|s| s.start <= *input) - 1
+    }
+
+    fn segment_end(&self, i: usize) -> Option<&T> {
+        self.segments.get(i + 1).map(|s| &s.start)
+    }
+
+    fn segment_len(&self, i: usize) -> Result<Option<T>, OverflowError> {
+        self.segment_end(i)
+            .map(|end| end.try_sub(&self.segments[i].start))
+            .transpose()
+    }
+
+    pub fn get(&self, input: &T) -> Result<T, OverflowError> {
+        let s = &self.segments[self.segment_index(input)];
+        s.destination.try_add(&input.try_sub(&s.start)?)
+    }
+
+    /// Images of the range `start..start + len` as `(start, len)` pairs, ordered by their
+    /// preimages.
+    pub fn get_range(&self, start: &T, len: &T) -> Result<Vec<(T, T)>, OverflowError> {
+        if len.is_zero() {
+            return Ok(vec![]);
+        }
+
+        let end = start.try_add(len)?;
+        let mut result = vec![];
+        for i in self.segment_index(start)..self.segments.len() {
+            let s = &self.segments[i];
+            if s.start >= end {
+                break;
+            }
+
+            let from = start.max(&s.start);
+            let to = match self.segment_end(i) {
+                Some(segment_end) => segment_end.min(&end),
+                None => &end,
+            };
+            result.push((
+                s.destination.try_add(&from.try_sub(&s.start)?)?,
+                to.try_sub(from)?,
+            ));
+        }
+
+        Ok(result)
+    }
+
+    /// Every input that is mapped to `output`.
+    pub fn preimages(&self, output: &T) -> Result<Vec<T>, OverflowError> {
+        let mut result = vec![];
+        for (i, s) in self.segments.iter().enumerate() {
+            if *output >= s.destination {
+                let offset = output.try_sub(&s.destination)?;
+                if self.segment_len(i)?.is_none_or(|len| offset < len) {
+                    result.push(s.start.try_add(&offset)?);
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Applies `self` first and `next` afterwards.
+    pub fn compose(&self, next: &Map<T>) -> Result<Map<T>, OverflowError> {
+        let mut segments = vec![];
+        for (i, s) in self.segments.iter().enumerate() {
+            // `None` for images reaching past the largest representable number
+            let image_end = self
+                .segment_len(i)?
+                .and_then(|len| s.destination.try_add(&len).ok());
+            for j in next.segment_index(&s.destination)..next.segments.len() {
+                let n = &next.segments[j];
+                if image_end.as_ref().is_some_and(|end| n.start >= *end) {
+                    break;
                 }
+
+                let from = (&s.destination).max(&n.start);
+                segments.push(Segment {
+                    start: s.start.try_add(&from.try_sub(&s.destination)?)?,
+                    destination: n.destination.try_add(&from.try_sub(&n.start)?)?,
+                });
             }
+        }
 
-            // if no entries match we use the identity mapping
-            results.push((start, len));
+        Ok(Map {
+            source: self.source.clone(),
+            destination: next.destination.clone(),
+            segments: merge(segments),
+        })
+    }
+
+    /// The inverse map, if this map is a bijection.
+    pub fn inverse(&self) -> Result<Option<Map<T>>, OverflowError> {
+        let mut images = (0..self.segments.len())
+            .map(|i| Ok((&self.segments[i], self.segment_len(i)?)))
+            .collect::<Result<Vec<_>, _>>()?;
+        images.sort_unstable_by(|(a, _), (b, _)| a.destination.cmp(&b.destination));
+
+        // the images have to tile the whole domain without gaps or overlaps
+        let mut expected = Some(T::zero());
+        for (s, len) in &images {
+            if expected.as_ref() != Some(&s.destination) {
+                return Ok(None);
+            }
+            expected = len.as_ref().and_then(|len| s.destination.try_add(len).ok());
         }
+        if expected.is_some() {
+            return Ok(None);
+        }
+
+        Ok(Some(Map {
+            source: self.destination.clone(),
+            destination: self.source.clone(),
+            segments: merge(
+                images
+                    .into_iter()
+                    .map(|(s, _)| Segment {
+                        start: s.destination.clone(),
+                        destination: s.start.clone(),
+                    })
+                    .collect(),
+            ),
+        }))
+    }
+}
 
-        results
+/// Joins adjacent segments that continue each other.
+fn merge<T: CheckedInteger>(segments: Vec<Segment<T>>) -> Vec<Segment<T>> {
+    let mut merged: Vec<Segment<T>> = Vec::with_capacity(segments.len());
+    for s in segments {
+        if let Some(prev) = merged.last() {
+            let continued = s
+                .start
+                .try_sub(&prev.start)
+                .and_then(|offset| prev.destination.try_add(&offset));
+            if continued.is_ok_and(|d| d == s.destination) {
+                continue;
+            }
+        }
+        merged.push(s);
     }
+    merged
 }
 
 #[derive(Debug)]
-pub struct MapEntry {
-    destination_start: u32,
-    source_start: u32,
-    range_length: u32,
+pub struct MapEntry<T> {
+    destination_start: T,
+    source_start: T,
+    range_length: T,
 }
 
-impl FromStr for MapEntry {
+impl<T: FromStr> FromStr for MapEntry<T> {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -152,17 +353,27 @@ impl FromStr for MapEntry {
     }
 }
 
-impl MapEntry {
-    fn convert(&self, input: u32) -> Option<u32> {
-        if input >= self.source_start {
-            let offset = input - self.source_start;
-            if offset < self.range_length {
-                return Some(self.destination_start + offset);
+fn lowest_location<T: CheckedInteger>(input: &Almanac<T>) -> Result<T, RouteError> {
+    let map = input.seed_to_location()?;
+    Ok(input
+        .initial
+        .iter()
+        .map(|n| map.get(n))
+        .process_results(|it| it.min())?
+        .unwrap())
+}
+
+fn lowest_location_of_ranges<T: CheckedInteger>(input: &Almanac<T>) -> Result<T, RouteError> {
+    let map = input.seed_to_location()?;
+    let mut lowest = None;
+    for (start, len) in input.initial.iter().tuples() {
+        for (location, _) in map.get_range(start, len)? {
+            if lowest.as_ref().is_none_or(|l| location < *l) {
+                lowest = Some(location);
             }
         }
-
-        None
     }
+    Ok(lowest.unwrap())
 }
 
 #[aoc_generator(day5)]
@@ -171,29 +382,33 @@ pub fn input_generator(input: &str) -> Almanac {
 }
 
 #[aoc(day5, part1)]
-pub fn part1(input: &Almanac) -> u32 {
-    input
-        .initial
-        .iter()
-        .map(|n| input.convert(*n))
-        .min()
-        .unwrap()
+pub fn part1(input: &Almanac) -> Result<u32, RouteError> {
+    lowest_location(input)
 }
 
 #[aoc(day5, part2)]
-pub fn part2(input: &Almanac) -> u32 {
-    let ranges = input
-        .initial
-        .iter()
-        .tuples()
-        .map(|(start, len)| (*start, *len))
-        .collect();
-    input
-        .convert_multi(ranges)
-        .iter()
-        .map(|(start, _len)| *start)
-        .min()
-        .unwrap()
+pub fn part2(input: &Almanac) -> Result<u32, RouteError> {
+    lowest_location_of_ranges(input)
+}
+
+#[aoc_generator(day5, part1, bigint)]
+pub fn input_generator_bigint_1(input: &str) -> Almanac<BigUint> {
+    input.parse().unwrap()
+}
+
+#[aoc(day5, part1, bigint)]
+pub fn part1_bigint(input: &Almanac<BigUint>) -> Result<BigUint, RouteError> {
+    lowest_location(input)
+}
+
+#[aoc_generator(day5, part2, bigint)]
+pub fn input_generator_bigint_2(input: &str) -> Almanac<BigUint> {
+    input.parse().unwrap()
+}
+
+#[aoc(day5, part2, bigint)]
+pub fn part2_bigint(input: &Almanac<BigUint>) -> Result<BigUint, RouteError> {
+    lowest_location_of_ranges(input)
 }
 
 #[cfg(test)]
@@ -238,11 +453,87 @@ humidity-to-location map:
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&input_generator(INPUT)), 35);
+        assert_eq!(part1(&input_generator(INPUT)), Ok(35));
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&input_generator(INPUT)), 46);
+        assert_eq!(part2(&input_generator(INPUT)), Ok(46));
+    }
+
+    #[test]
+    fn test_bigint() {
+        assert_eq!(
+            part1_bigint(&input_generator_bigint_1(INPUT)),
+            Ok(BigUint::from(35u32))
+        );
+        assert_eq!(
+            part2_bigint(&input_generator_bigint_2(INPUT)),
+            Ok(BigUint::from(46u32))
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        const INPUT: &str = r#"seeds: 4294967290 10
+
+seed-to-location map:
+0 0 1"#;
+
+        assert_eq!(part2(&input_generator(INPUT)), Err(OverflowError.into()));
+        assert_eq!(
+            part2_bigint(&input_generator_bigint_2(INPUT)),
+            Ok(BigUint::from(4294967290u32))
+        );
+    }
+
+    #[test]
+    fn test_seed_to_location() {
+        let almanac = input_generator(INPUT);
+        let map = almanac.seed_to_location().unwrap();
+        for (seed, location) in [(79, 82), (14, 43), (55, 86), (13, 35)] {
+            assert_eq!(map.get(&seed), Ok(location));
+        }
+
+        let inverse = map.inverse().unwrap().unwrap();
+        assert_eq!(
+            (inverse.source(), inverse.destination()),
+            ("location", "seed")
+        );
+        for location in 0..200 {
+            assert_eq!(map.get(&inverse.get(&location).unwrap()), Ok(location));
+            assert_eq!(
+                map.preimages(&location),
+                Ok(vec![inverse.get(&location).unwrap()])
+            );
+        }
+    }
+
+    #[test]
+    fn test_route() {
+        let almanac = input_generator(INPUT);
+        let map = almanac.route("soil", "humidity").unwrap();
+        // soil 81 -> fertilizer 81 -> water 81 -> light 74 -> temperature 78 -> humidity 78
+        assert_eq!(map.get(&81), Ok(78));
+        assert_eq!(
+            almanac.route("location", "seed"),
+            Err(RouteError::NoRoute("location".into(), "seed".into()))
+        );
+    }
+
+    #[test]
+    fn test_merged_segments() {
+        let map: Map<u32> = "a-to-b map:\n10 10 5\n15 15 5\n0 20 5\n20 0 10"
+            .parse()
+            .unwrap();
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.get(&3), Ok(23));
+        assert_eq!(map.get(&12), Ok(12));
+        assert_eq!(map.get(&22), Ok(2));
+        assert_eq!(map.get(&1000), Ok(1000));
+        assert_eq!(map.inverse(), Ok(None));
+        assert_eq!(map.preimages(&2), Ok(vec![22]));
+        assert_eq!(map.preimages(&12), Ok(vec![12]));
+        assert_eq!(map.preimages(&21), Ok(vec![1]));
     }
 }
@@ -1,5 +1,8 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
+use num::{BigInt, BigUint, Integer, One, Signed, ToPrimitive, Zero};
+
+use crate::common::{checked_product, OverflowError};
 
 #[derive(Debug)]
 pub struct Race {
@@ -28,52 +31,179 @@ pub fn input_generator(input: &str) -> Vec<Race> {
         .collect()
 }
 
-fn count_better_button_times(total_time: u64, distance_to_beat: u64) -> u64 {
-    // we want to solve the inequality distance_travelled(total_time, button_time) > distance_to_beat
-    // distance_travelled(total_time, button_time) = -button_time^2 + total_time*button_time
-    // solve the equality distance_travelled(total_time, button_time) = distance_to_beat, which has 2 solutions
-    // the inequality holds between those
-    // then use smart rounding to count the integer values between those roots
-    let minus_p_half = total_time as f64 / 2.0;
-    let p_half_sq = minus_p_half.powi(2);
-    if p_half_sq <= distance_to_beat as f64 {
-        return 0;
+/// Distance travelled as a quadratic polynomial `a * x^2 + b * x + c` in the hold time `x`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Quadratic {
+    pub a: BigInt,
+    pub b: BigInt,
+    pub c: BigInt,
+}
+
+impl Quadratic {
+    pub fn new(a: impl Into<BigInt>, b: impl Into<BigInt>, c: impl Into<BigInt>) -> Self {
+        Self {
+            a: a.into(),
+            b: b.into(),
+            c: c.into(),
+        }
+    }
+
+    /// The boat from the puzzle, which moves with the hold time as speed for the rest of the race.
+    pub fn boat(total_time: impl Into<BigInt>) -> Self {
+        Self::new(-1, total_time, 0)
+    }
+
+    pub fn eval(&self, x: &BigInt) -> BigInt {
+        (&self.a * x + &self.b) * x + &self.c
+    }
+
+    /// Number of integers `x` in `low..=high` with `self.eval(x) > threshold`.
+    pub fn count_above(&self, threshold: &BigInt, low: &BigInt, high: &BigInt) -> BigUint {
+        let f = Self {
+            c: &self.c - threshold,
+            ..self.clone()
+        };
+        let count =
+            |(l, r): (BigInt, BigInt)| -> BigInt { r.min(high.clone()) - l.max(low.clone()) + 1 };
+
+        let count = if f.a.is_zero() {
+            count(f.linear_interval(low, high))
+        } else if f.a.is_negative() {
+            f.concave_interval(true).map(count).unwrap_or_default()
+        } else {
+            // the polynomial is positive outside of the interval where its negation is >= 0
+            let negated = Self::new(-&f.a, -&f.b, -&f.c);
+            let total = count((low.clone(), high.clone()));
+            total
+                - negated
+                    .concave_interval(false)
+                    .map(count)
+                    .filter(BigInt::is_positive)
+                    .unwrap_or_default()
+        };
+        count.to_biguint().unwrap_or_default()
     }
 
-    let disc_sqrt = (p_half_sq - distance_to_beat as f64).sqrt();
-    let t1 = minus_p_half + disc_sqrt;
-    let t2 = minus_p_half - disc_sqrt;
-    // we always have 0 <= t2 < t1
-
-    let t1_i = (t1 - 1.0).ceil() as u64;
-    let t2_i = (t2 + 1.0).floor() as u64;
-    if t1_i < t2_i {
-        // this can happen when t1 and t2 are really close and the rounding moves them past each other
-        0
-    } else {
-        t1_i - t2_i + 1
+    /// Integers where the linear polynomial `b * x + c` is positive, bounded by `low..=high`.
+    fn linear_interval(&self, low: &BigInt, high: &BigInt) -> (BigInt, BigInt) {
+        let (b, c) = (&self.b, &self.c);
+        if b.is_zero() {
+            if c.is_positive() {
+                (low.clone(), high.clone())
+            } else {
+                (BigInt::one(), BigInt::zero())
+            }
+        } else if b.is_positive() {
+            ((-c).div_floor(b) + 1, high.clone())
+        } else {
+            (low.clone(), c.div_ceil(&-b) - 1)
+        }
+    }
+
+    /// Integers where a polynomial with negative `a` is positive (or non-negative if not `strict`),
+    /// as an inclusive range.
+    fn concave_interval(&self, strict: bool) -> Option<(BigInt, BigInt)> {
+        let holds = |x: &BigInt| {
+            let y = self.eval(x);
+            if strict {
+                y.is_positive()
+            } else {
+                !y.is_negative()
+            }
+        };
+
+        // the maximum over the integers is next to the vertex -b / 2a
+        let two_a = &self.a * 2;
+        let vertex = (-&self.b).div_floor(&two_a);
+        let next = &vertex + 1;
+        let top = if self.eval(&next) > self.eval(&vertex) {
+            next
+        } else {
+            vertex
+        };
+        if !holds(&top) {
+            return None;
+        }
+
+        // the integer square root makes the roots off by at most one, fix them by evaluating
+        let disc: BigInt = &self.b * &self.b - 4 * &self.a * &self.c;
+        let sqrt = if disc.is_negative() {
+            BigInt::zero()
+        } else {
+            disc.sqrt()
+        };
+        let r1 = (-&self.b + &sqrt).div_floor(&two_a);
+        let r2 = (-&self.b - &sqrt).div_floor(&two_a);
+
+        let mut left = r1.clone().min(r2.clone()).min(top.clone());
+        while holds(&(&left - 1)) {
+            left -= 1;
+        }
+        while !holds(&left) {
+            left += 1;
+        }
+
+        let mut right = r1.max(r2).max(top);
+        while holds(&(&right + 1)) {
+            right += 1;
+        }
+        while !holds(&right) {
+            right -= 1;
+        }
+
+        Some((left, right))
     }
 }
 
+fn count_better_button_times(total_time: u64, distance_to_beat: u64) -> Result<u64, OverflowError> {
+    Quadratic::boat(total_time)
+        .count_above(
+            &distance_to_beat.into(),
+            &BigInt::zero(),
+            &total_time.into(),
+        )
+        .to_u64()
+        .ok_or(OverflowError)
+}
+
 #[aoc(day6, part1)]
-pub fn part1(input: &[Race]) -> u64 {
+pub fn part1(input: &[Race]) -> Result<u64, OverflowError> {
     input
         .iter()
         .map(|r| count_better_button_times(r.time, r.distance))
-        .product()
+        .process_results(|it| checked_product(it))?
 }
 
-#[aoc(day6, part2)]
-pub fn part2(input: &[Race]) -> u64 {
-    fn merge_numbers(mut it: impl Iterator<Item = u64>) -> u64 {
-        it.join("").parse().unwrap()
-    }
+#[aoc(day6, part1, bigint)]
+pub fn part1_bigint(input: &[Race]) -> Result<BigUint, OverflowError> {
+    input
+        .iter()
+        .map(|r| count_better_button_times(r.time, r.distance).map(BigUint::from))
+        .process_results(|it| checked_product(it))?
+}
+
+fn merge_numbers(mut it: impl Iterator<Item = u64>) -> BigUint {
+    it.join("").parse().unwrap()
+}
 
-    let time = merge_numbers(input.iter().map(|r| r.time));
-    let distance = merge_numbers(input.iter().map(|r| r.distance));
+#[aoc(day6, part2)]
+pub fn part2(input: &[Race]) -> Result<u64, OverflowError> {
+    let time = merge_numbers(input.iter().map(|r| r.time))
+        .to_u64()
+        .ok_or(OverflowError)?;
+    let distance = merge_numbers(input.iter().map(|r| r.distance))
+        .to_u64()
+        .ok_or(OverflowError)?;
     count_better_button_times(time, distance)
 }
 
+#[aoc(day6, part2, bigint)]
+pub fn part2_bigint(input: &[Race]) -> BigUint {
+    let time: BigInt = merge_numbers(input.iter().map(|r| r.time)).into();
+    let distance = merge_numbers(input.iter().map(|r| r.distance)).into();
+    Quadratic::boat(time.clone()).count_above(&distance, &BigInt::zero(), &time)
+}
+
 #[cfg(test)]
 mod tests {
     use pretty_assertions::assert_eq;
@@ -85,11 +215,69 @@ Distance:  9  40  200"#;
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&input_generator(INPUT)), 288);
+        assert_eq!(part1(&input_generator(INPUT)), Ok(288));
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&input_generator(INPUT)), 71503);
+        assert_eq!(part2(&input_generator(INPUT)), Ok(71503));
+    }
+
+    #[test]
+    fn test_part2_overflow() {
+        const INPUT: &str = r#"Time:      1000000000  1000000000000
+Distance:  1  1"#;
+
+        assert_eq!(part2(&input_generator(INPUT)), Err(OverflowError));
+        assert_eq!(
+            part2_bigint(&input_generator(INPUT)),
+            "10000000000999999999999".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_exact() {
+        // above 2^53, where f64 can't tell the distances apart anymore
+        let half = (1 << 31) + 1;
+        assert_eq!(count_better_button_times(2 * half, half * half - 1), Ok(1));
+        assert_eq!(count_better_button_times(2 * half, half * half), Ok(0));
+        assert_eq!(
+            count_better_button_times(2 * half + 1, half * (half + 1) - 1),
+            Ok(2)
+        );
+    }
+
+    #[test]
+    fn test_quadratic() {
+        let count = |q: Quadratic, threshold: i64, low: i64, high: i64| {
+            q.count_above(&threshold.into(), &low.into(), &high.into())
+        };
+        // twice the drag and a constant start penalty
+        assert_eq!(
+            count(Quadratic::new(-2, 30, -8), 100, 0, 15),
+            BigUint::from(2u32)
+        );
+        // convex and linear models
+        assert_eq!(
+            count(Quadratic::new(1, -10, 0), 0, 0, 20),
+            BigUint::from(10u32)
+        );
+        assert_eq!(
+            count(Quadratic::new(1, -10, 0), -100, 0, 20),
+            BigUint::from(21u32)
+        );
+        assert_eq!(
+            count(Quadratic::new(0, 3, -7), 0, 0, 10),
+            BigUint::from(8u32)
+        );
+        assert_eq!(
+            count(Quadratic::new(0, -3, 7), 0, 0, 10),
+            BigUint::from(3u32)
+        );
+        assert_eq!(
+            count(Quadratic::new(0, 0, 1), 0, 0, 10),
+            BigUint::from(11u32)
+        );
+        assert_eq!(count(Quadratic::new(-1, 0, 0), 0, -5, 5), BigUint::zero());
     }
 }
@@ -1,157 +1,258 @@
+use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
+use thiserror::Error;
 
-use crate::common::parse_lines;
-
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
-pub enum HandType {
-    HighCard,
-    OnePair,
-    TwoPair,
-    ThreeOfAKind,
-    FullHouse,
-    FourOfAKind,
-    FiveOfAKind,
-}
-
-impl HandType {
-    fn find(hand: &Hand) -> Self {
-        let counts = hand.cards.iter().counts();
-        let jokers = counts.get(&Card::Joker);
-        match counts.len() {
-            1 => Self::FiveOfAKind,
-            2 => {
-                if jokers.is_some() {
-                    Self::FiveOfAKind
-                } else if *counts.values().max().unwrap() == 4 {
-                    Self::FourOfAKind
-                } else {
-                    Self::FullHouse
-                }
-            }
-            3 => {
-                if *counts.values().max().unwrap() == 3 {
-                    if jokers.is_some() {
-                        Self::FourOfAKind
-                    } else {
-                        Self::ThreeOfAKind
-                    }
-                } else {
-                    match jokers {
-                        None => Self::TwoPair,
-                        Some(1) => Self::FullHouse,
-                        Some(2) => Self::FourOfAKind,
-                        _ => unreachable!(),
+use crate::common::{checked_sum, parse_lines, CheckedInteger, OverflowError};
+
+/// Sizes of the groups of equal cards in descending order, with the wild cards added to the
+/// largest group. Comparing them lexicographically orders the hand types.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct HandType(pub Vec<usize>);
+
+impl Display for HandType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.0.as_slice() {
+            [5] => write!(f, "five of a kind"),
+            [4, 1] => write!(f, "four of a kind"),
+            [3, 2] => write!(f, "full house"),
+            [3, 1, 1] => write!(f, "three of a kind"),
+            [2, 2, 1] => write!(f, "two pair"),
+            [2, 1, 1, 1] => write!(f, "one pair"),
+            [1, 1, 1, 1, 1] => write!(f, "high card"),
+            signature => write!(f, "{}", signature.iter().join("+")),
+        }
+    }
+}
+
+/// How hands of the same type are ordered.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum TieBreak {
+    /// compare the cards in the order they were dealt
+    #[default]
+    InOrder,
+    /// compare the cards from highest to lowest, like poker
+    Sorted,
+}
+
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
+pub enum RulesError {
+    #[error("hand {0} does not have {1} cards")]
+    HandSize(Hand, usize),
+    #[error("hand {0} contains the unknown card {1}")]
+    UnknownCard(Hand, char),
+    #[error(transparent)]
+    Overflow(#[from] OverflowError),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Rules {
+    pub hand_size: usize,
+    /// card ranks from lowest to highest
+    pub order: Vec<char>,
+    pub wild: Vec<char>,
+    pub tie_break: TieBreak,
+}
+
+impl Rules {
+    pub fn standard() -> Self {
+        Self {
+            hand_size: 5,
+            order: "23456789TJQKA".chars().collect(),
+            wild: vec![],
+            tie_break: TieBreak::InOrder,
+        }
+    }
+
+    /// Jacks are jokers, they are wild but the weakest card on their own.
+    pub fn jokers() -> Self {
+        Self {
+            order: "J23456789TQKA".chars().collect(),
+            wild: vec!['J'],
+            ..Self::standard()
+        }
+    }
+
+    pub fn hand_type(&self, hand: &Hand) -> HandType {
+        let counts = hand
+            .cards
+            .iter()
+            .filter(|c| !self.wild.contains(c))
+            .counts();
+        let wild = hand.cards.len() - counts.values().sum::<usize>();
+        let mut signature: Vec<_> = counts.into_values().sorted_unstable().rev().collect();
+        match signature.first_mut() {
+            Some(largest) => *largest += wild,
+            None if wild > 0 => signature.push(wild),
+            None => {}
+        }
+        HandType(signature)
+    }
+
+    /// Ranks of the cards in the order they are compared.
+    fn tie_break_ranks(&self, hand: &Hand) -> Result<Vec<usize>, RulesError> {
+        let mut ranks = hand
+            .cards
+            .iter()
+            .map(|&c| {
+                self.order
+                    .iter()
+                    .position(|&o| o == c)
+                    .ok_or_else(|| RulesError::UnknownCard(hand.clone(), c))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if self.tie_break == TieBreak::Sorted {
+            ranks.sort_unstable_by(|a, b| b.cmp(a));
+        }
+        Ok(ranks)
+    }
+
+    fn key(&self, hand: &Hand) -> Result<(HandType, Vec<usize>), RulesError> {
+        if hand.cards.len() != self.hand_size {
+            return Err(RulesError::HandSize(hand.clone(), self.hand_size));
+        }
+
+        Ok((self.hand_type(hand), self.tie_break_ranks(hand)?))
+    }
+
+    /// All bids from the weakest to the strongest hand.
+    pub fn rank<'a>(&self, bids: &'a [Bid]) -> Result<Vec<Ranking<'a>>, RulesError> {
+        let mut keyed = bids
+            .iter()
+            .map(|b| Ok((self.key(&b.hand)?, b)))
+            .collect::<Result<Vec<_>, RulesError>>()?;
+        keyed.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut rankings: Vec<Ranking> = Vec::with_capacity(keyed.len());
+        for (i, ((hand_type, ranks), bid)) in keyed.iter().enumerate() {
+            let reason = match i.checked_sub(1).map(|j| &keyed[j]) {
+                None => Reason::Weakest,
+                Some(((prev_type, _), _)) if prev_type < hand_type => Reason::HandType {
+                    beats: prev_type.clone(),
+                },
+                Some(((_, prev_ranks), prev_bid)) => {
+                    match ranks.iter().zip(prev_ranks).position(|(a, b)| a != b) {
+                        Some(position) => Reason::TieBreak {
+                            position,
+                            card: self.order[ranks[position]],
+                            beats: self.order[prev_ranks[position]],
+                        },
+                        None => Reason::Tie {
+                            with: prev_bid.hand.clone(),
+                        },
                     }
                 }
-            }
-            4 => {
-                if jokers.is_some() {
-                    Self::ThreeOfAKind
-                } else {
-                    Self::OnePair
-                }
-            }
-            5 => {
-                if jokers.is_some() {
-                    Self::OnePair
-                } else {
-                    Self::HighCard
-                }
-            }
-            _ => unreachable!(),
+            };
+            rankings.push(Ranking {
+                bid,
+                rank: i + 1,
+                hand_type: hand_type.clone(),
+                reason,
+            });
         }
+
+        Ok(rankings)
+    }
+
+    pub fn total_winnings(&self, bids: &[Bid]) -> Result<u32, RulesError> {
+        let winnings = self
+            .rank(bids)?
+            .iter()
+            .map(|r| u32::try_from_usize(r.rank)?.try_mul(&r.bid.bid))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(checked_sum(winnings)?)
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
-pub enum Card {
-    Joker,
-    Two,
-    Three,
-    Four,
-    Five,
-    Six,
-    Seven,
-    Eight,
-    Nine,
-    Ten,
-    Jack,
-    Queen,
-    King,
-    Ace,
-}
-
-impl TryFrom<char> for Card {
-    type Error = ();
-
-    fn try_from(value: char) -> Result<Self, Self::Error> {
-        Ok(match value {
-            '2' => Self::Two,
-            '3' => Self::Three,
-            '4' => Self::Four,
-            '5' => Self::Five,
-            '6' => Self::Six,
-            '7' => Self::Seven,
-            '8' => Self::Eight,
-            '9' => Self::Nine,
-            'T' => Self::Ten,
-            'J' => Self::Jack,
-            'Q' => Self::Queen,
-            'K' => Self::King,
-            'A' => Self::Ace,
-            _ => {
-                return Err(());
-            }
-        })
+/// Why a hand is ranked above the hand directly below it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Reason {
+    Weakest,
+    HandType {
+        beats: HandType,
+    },
+    /// same hand type, decided by the cards at `position` in tie-break order
+    TieBreak {
+        position: usize,
+        card: char,
+        beats: char,
+    },
+    /// indistinguishable hands keep their input order
+    Tie {
+        with: Hand,
+    },
+}
+
+impl Display for Reason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Reason::Weakest => write!(f, "weakest hand"),
+            Reason::HandType { beats } => write!(f, "beats {beats}"),
+            Reason::TieBreak {
+                position,
+                card,
+                beats,
+            } => write!(f, "card {} {card} beats {beats}", position + 1),
+            Reason::Tie { with } => write!(f, "tied with {with}"),
+        }
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Ranking<'a> {
+    pub bid: &'a Bid,
+    /// 1 for the weakest hand
+    pub rank: usize,
+    pub hand_type: HandType,
+    pub reason: Reason,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct Hand {
-    cards: [Card; 5],
+    cards: Vec<char>,
 }
 
 impl FromStr for Hand {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.len() != 5 || !s.is_ascii() {
+        if s.is_empty() || s.chars().any(char::is_whitespace) {
             return Err(());
         }
 
-        let cards = s
-            .chars()
-            .map(TryFrom::try_from)
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|_| ())?
-            .try_into()
-            .map_err(|_| ())?;
-        Ok(Hand { cards })
+        Ok(Hand {
+            cards: s.chars().collect(),
+        })
+    }
+}
+
+impl Display for Hand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cards.iter().collect::<String>())
     }
 }
 
 impl Hand {
-    fn enable_joker(&mut self) {
-        self.cards.iter_mut().for_each(|c| {
-            if matches!(c, Card::Jack) {
-                *c = Card::Joker;
-            }
-        })
+    pub fn cards(&self) -> &[char] {
+        &self.cards
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Bid {
     hand: Hand,
     bid: u32,
 }
 
 impl Bid {
-    fn enable_joker(&mut self) {
-        self.hand.enable_joker()
+    pub fn hand(&self) -> &Hand {
+        &self.hand
+    }
+
+    pub fn bid(&self) -> u32 {
+        self.bid
     }
 }
 
@@ -173,24 +274,13 @@ pub fn input_generator(input: &str) -> Vec<Bid> {
 }
 
 #[aoc(day7, part1)]
-pub fn part1(input: &[Bid]) -> u32 {
-    let mut bids = input.to_vec();
-    bids.sort_by_cached_key(|b| (HandType::find(&b.hand), b.hand));
-    bids.iter()
-        .enumerate()
-        .map(|(n, bid)| (n + 1) as u32 * bid.bid)
-        .sum()
+pub fn part1(input: &[Bid]) -> Result<u32, RulesError> {
+    Rules::standard().total_winnings(input)
 }
 
 #[aoc(day7, part2)]
-pub fn part2(input: &[Bid]) -> u32 {
-    let mut bids = input.to_vec();
-    bids.iter_mut().for_each(|b| b.enable_joker());
-    bids.sort_by_cached_key(|b| (HandType::find(&b.hand), b.hand));
-    bids.iter()
-        .enumerate()
-        .map(|(n, bid)| (n + 1) as u32 * bid.bid)
-        .sum()
+pub fn part2(input: &[Bid]) -> Result<u32, RulesError> {
+    Rules::jokers().total_winnings(input)
 }
 
 #[cfg(test)]
@@ -228,21 +318,86 @@ JJJJ2 41"#;
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&input_generator(INPUT)), 6440);
+        assert_eq!(part1(&input_generator(INPUT)), Ok(6440));
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&input_generator(INPUT)), 5905);
+        assert_eq!(part2(&input_generator(INPUT)), Ok(5905));
     }
 
     #[test]
     fn test_part1_2() {
-        assert_eq!(part1(&input_generator(INPUT_2)), 6592);
+        assert_eq!(part1(&input_generator(INPUT_2)), Ok(6592));
     }
 
     #[test]
     fn test_part2_2() {
-        assert_eq!(part2(&input_generator(INPUT_2)), 6839);
+        assert_eq!(part2(&input_generator(INPUT_2)), Ok(6839));
+    }
+
+    #[test]
+    fn test_overflow() {
+        let input = input_generator("AAAAA 4000000000\nKKKKK 4000000000");
+        assert_eq!(part1(&input), Err(RulesError::Overflow(OverflowError)));
+    }
+
+    #[test]
+    fn test_rank() {
+        let bids = input_generator(INPUT);
+        let rankings = Rules::standard().rank(&bids).unwrap();
+        assert_eq!(
+            rankings
+                .iter()
+                .map(|r| (r.bid.hand().to_string(), r.reason.to_string()))
+                .collect::<Vec<_>>(),
+            [
+                ("32T3K".to_string(), "weakest hand".to_string()),
+                ("KTJJT".to_string(), "beats one pair".to_string()),
+                ("KK677".to_string(), "card 2 K beats T".to_string()),
+                ("T55J5".to_string(), "beats two pair".to_string()),
+                ("QQQJA".to_string(), "card 1 Q beats T".to_string()),
+            ]
+        );
+
+        let rankings = Rules::jokers().rank(&bids).unwrap();
+        assert_eq!(rankings[4].bid.hand().to_string(), "KTJJT");
+        assert_eq!(rankings[4].hand_type, HandType(vec![4, 1]));
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        let bids = input_generator("AAK2 1\n2KKK 2\n9*99 4\nAK23 8\nKKK3 16");
+        let rules = Rules {
+            hand_size: 4,
+            order: "*23456789TJQKA".chars().collect(),
+            wild: vec!['*'],
+            tie_break: TieBreak::Sorted,
+        };
+        let rankings = rules.rank(&bids).unwrap();
+        assert_eq!(
+            rankings.iter().map(|r| r.bid.bid()).collect::<Vec<_>>(),
+            [8, 1, 2, 16, 4]
+        );
+        assert_eq!(
+            rankings[3].reason,
+            Reason::TieBreak {
+                position: 3,
+                card: '3',
+                beats: '2'
+            }
+        );
+        assert_eq!(
+            Rules::standard().rank(&bids),
+            Err(RulesError::HandSize("AAK2".parse().unwrap(), 5))
+        );
+        assert_eq!(
+            Rules {
+                hand_size: 4,
+                ..Rules::standard()
+            }
+            .rank(&bids),
+            Err(RulesError::UnknownCard("9*99".parse().unwrap(), '*'))
+        );
     }
 }
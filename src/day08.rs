@@ -6,6 +6,8 @@ use num::integer::ExtendedGcd;
 use num::Integer;
 use rustc_hash::FxHashMap;
 
+use crate::common::{Dot, Interner};
+
 #[derive(Debug)]
 pub enum Instruction {
     L,
@@ -27,7 +29,9 @@ impl TryFrom<char> for Instruction {
 #[derive(Debug)]
 pub struct Map {
     instructions: Vec<Instruction>,
-    graph: FxHashMap<String, (String, String)>,
+    names: Interner,
+    /// `(left, right)` children, indexed by node id
+    graph: Vec<(u32, u32)>,
 }
 
 impl FromStr for Map {
@@ -40,77 +44,121 @@ impl FromStr for Map {
             .chars()
             .map(Instruction::try_from)
             .collect::<Result<_, _>>()?;
-        let graph = graph
+        let mut names = Interner::new();
+        let edges: Vec<_> = graph
             .trim()
             .lines()
             .map(|l| {
                 let (node, children) = l.split_once('=').ok_or(())?;
-                let node = node.trim().to_string();
-                let children = children
+                let node = names.intern(node.trim());
+                let (left, right) = children
                     .trim_matches(|c: char| c == '(' || c == ')' || c.is_whitespace())
                     .split(',')
                     .map(str::trim)
-                    .map(|s| s.trim().to_string())
                     .collect_tuple()
                     .ok_or(())?;
-                Ok((node, children))
+                Ok((node, (names.intern(left), names.intern(right))))
             })
             .collect::<Result<_, _>>()?;
 
+        let mut graph = vec![None; names.len()];
+        for (node, children) in edges {
+            graph[node as usize] = Some(children);
+        }
+
         Ok(Map {
             instructions,
-            graph,
+            // every referenced node needs to be defined
+            graph: graph.into_iter().collect::<Option<_>>().ok_or(())?,
+            names,
         })
     }
 }
 
-#[derive(Debug)]
-struct LoopInformationSystem {
+impl Map {
+    /// Renders the node map with edges labelled by the instruction that follows them.
+    pub fn to_dot(&self) -> Dot {
+        let mut dot = Dot::new(true);
+        for (node, name) in self.names.iter() {
+            dot.node(name, &[]);
+            let (left, right) = self.graph[node as usize];
+            if left == right {
+                dot.edge(name, self.names.name(left), &[("label", "L, R")]);
+            } else {
+                dot.edge(name, self.names.name(left), &[("label", "L")]);
+                dot.edge(name, self.names.name(right), &[("label", "R")]);
+            }
+        }
+
+        dot
+    }
+}
+
+impl Map {
+    /// Loop analysis for every ghost starting on a node matching `start`.
+    pub fn analyze(
+        &self,
+        start: impl Fn(&str) -> bool,
+        end: impl Fn(&str) -> bool,
+    ) -> LoopInformationSystem {
+        LoopInformationSystem::create(self, start, end)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LoopInformationSystem {
     infos: Vec<LoopInformation>,
 }
 
 impl LoopInformationSystem {
-    fn create(map: &Map, start: impl Fn(&str) -> bool, end: impl Fn(&str) -> bool) -> Self {
+    pub fn create(map: &Map, start: impl Fn(&str) -> bool, end: impl Fn(&str) -> bool) -> Self {
+        let is_end: Vec<_> = map.names.iter().map(|(_, name)| end(name)).collect();
+        let end = |node: u32| is_end[node as usize];
         Self {
             infos: map
-                .graph
-                .keys()
-                .filter(|n| start(n.as_str()))
-                .map(|start| {
+                .names
+                .iter()
+                .filter(|(_, name)| start(name))
+                .map(|(start, start_name)| {
                     // (node, instruction_offset) -> index
-                    let mut all_nodes: FxHashMap<(&str, usize), usize> = FxHashMap::default();
-                    let mut current = start.as_str();
+                    let mut all_nodes: FxHashMap<(u32, usize), usize> = FxHashMap::default();
+                    let mut path = vec![];
+                    let mut current = start;
                     for (n, (instruction_offset, instruction)) in
                         map.instructions.iter().enumerate().cycle().enumerate()
                     {
                         if let Some(&loop_start) = all_nodes.get(&(current, instruction_offset)) {
-                            // possible optimization: use symmetries in instructions & graph to reduce the cycle length
-                            // for that we need to find the shortest subcycle by just looking at the nodes, not the instruction offset
-                            let statics = all_nodes
-                                .iter()
-                                .filter(|((node, _), index)| **index < loop_start && end(node))
-                                .map(|(_, index)| *index as i64)
-                                .sorted()
+                            let statics = (0..loop_start)
+                                .filter(|&i| end(path[i]))
+                                .map(|i| i as i64)
                                 .collect();
-                            let loop_length = (n - loop_start) as i64;
-                            let dynamics = all_nodes
-                                .iter()
-                                .filter(|((node, _), index)| **index >= loop_start && end(node))
-                                .map(|(_, index)| *index as i64)
+                            let cycle = &path[loop_start..];
+                            let node_period = shortest_period(cycle);
+                            let dynamics = (loop_start..loop_start + node_period)
+                                .filter(|&i| end(path[i]))
+                                .map(|i| i as i64)
                                 .collect();
                             return LoopInformation {
+                                start: start_name.to_string(),
+                                loop_entry: (
+                                    map.names.name(current).to_string(),
+                                    instruction_offset,
+                                ),
+                                loop_start: loop_start as i64,
                                 statics,
-                                loop_length,
+                                loop_length: cycle.len() as i64,
+                                node_period: node_period as i64,
                                 dynamics,
                             };
                         } else {
                             all_nodes.insert((current, instruction_offset), n);
+                            path.push(current);
                         }
 
-                        let children = &map.graph[current];
+                        let (left, right) = map.graph[current as usize];
                         current = match instruction {
-                            Instruction::L => children.0.as_str(),
-                            Instruction::R => children.1.as_str(),
+                            Instruction::L => left,
+                            Instruction::R => right,
                         };
                     }
 
@@ -120,6 +168,10 @@ impl LoopInformationSystem {
         }
     }
 
+    pub fn infos(&self) -> &[LoopInformation] {
+        &self.infos
+    }
+
     fn has_dynamic_solution(&self) -> bool {
         !self.infos.is_empty() && self.infos.iter().all(LoopInformation::has_dynamic_solution)
     }
@@ -128,7 +180,8 @@ impl LoopInformationSystem {
         !self.infos.is_empty() && self.infos.iter().all(|li| li.is_solution(n))
     }
 
-    fn solve(&self) -> Option<i64> {
+    /// The first step at which all ghosts are on an end node at the same time.
+    pub fn solve(&self) -> Option<i64> {
         // first try the statics
         if let Some(n) = self
             .infos
@@ -160,30 +213,61 @@ impl LoopInformationSystem {
             }
         }
 
-        fn solve_lcs(lcs: &[LinearCongruence]) -> Option<i64> {
-            lcs.iter()
-                .try_fold(LinearCongruence::default(), |acc, x| solve_two_lc(&acc, x))
-                .map(|lc| lc.find_solution())
+        // combine the ghosts one after another, so the candidates are the distinct residues modulo
+        // the lcm of the loops so far instead of every combination of in-loop hits
+        let mut candidates = vec![LinearCongruence::default()];
+        for li in self
+            .infos
+            .iter()
+            .sorted_by_key(|li| li.dynamics.len() as i64 * li.node_period)
+        {
+            // residue -> smallest minimum
+            let mut next: FxHashMap<i64, LinearCongruence> = FxHashMap::default();
+            for a in &candidates {
+                for b in li.congruences() {
+                    if let Some(lc) = solve_two_lc(a, &b) {
+                        next.entry(lc.value)
+                            .and_modify(|e| e.minimum = e.minimum.min(lc.minimum))
+                            .or_insert(lc);
+                    }
+                }
+            }
+
+            if next.is_empty() {
+                return None;
+            }
+            candidates = next.into_values().collect();
         }
 
-        self.infos
-            .iter()
-            .map(|li| {
-                li.dynamics
-                    .iter()
-                    .map(|n| LinearCongruence::new(*n, li.loop_length))
-            })
-            .multi_cartesian_product()
-            .filter_map(|lcs| solve_lcs(&lcs))
-            .min()
+        candidates.iter().map(LinearCongruence::find_solution).min()
     }
 }
 
-#[derive(Debug)]
-struct LoopInformation {
-    statics: Vec<i64>,
-    loop_length: i64,
-    dynamics: Vec<i64>,
+/// Smallest `p` such that the cyclic sequence is made of repetitions of its first `p` elements.
+fn shortest_period<T: Eq>(cycle: &[T]) -> usize {
+    let len = cycle.len();
+    (1..=len)
+        .filter(|p| len.is_multiple_of(*p))
+        .find(|&p| (p..len).all(|i| cycle[i] == cycle[i - p]))
+        .unwrap()
+}
+
+/// How a ghost moves through the map: after `loop_start` steps it enters a loop of
+/// `(node, instruction offset)` states that it never leaves again.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LoopInformation {
+    pub start: String,
+    /// node and instruction offset of the first state in the loop
+    pub loop_entry: (String, usize),
+    pub loop_start: i64,
+    /// steps before the loop that end on an end node, sorted
+    pub statics: Vec<i64>,
+    pub loop_length: i64,
+    /// shortest period of just the nodes in the loop, divides `loop_length`
+    pub node_period: i64,
+    /// steps in the first `node_period` steps of the loop that end on an end node, sorted;
+    /// they repeat every `node_period` steps
+    pub dynamics: Vec<i64>,
 }
 
 impl LoopInformation {
@@ -191,13 +275,14 @@ impl LoopInformation {
         !self.dynamics.is_empty()
     }
 
+    fn congruences(&self) -> impl Iterator<Item = LinearCongruence> + '_ {
+        self.dynamics
+            .iter()
+            .map(|v| LinearCongruence::new(*v, self.node_period))
+    }
+
     fn is_solution(&self, n: &i64) -> bool {
-        self.statics.binary_search(n).is_ok()
-            || self
-                .dynamics
-                .iter()
-                .map(|v| LinearCongruence::new(*v, self.loop_length))
-                .any(|lc| lc.is_solution(n))
+        self.statics.binary_search(n).is_ok() || self.congruences().any(|lc| lc.is_solution(n))
     }
 }
 
@@ -258,13 +343,13 @@ pub fn input_generator(input: &str) -> Map {
 
 #[aoc(day8, part1)]
 pub fn part1(input: &Map) -> i64 {
-    let lis = LoopInformationSystem::create(input, |n| n == "AAA", |n| n == "ZZZ");
+    let lis = input.analyze(|n| n == "AAA", |n| n == "ZZZ");
     lis.solve().unwrap()
 }
 
 #[aoc(day8, part2)]
 pub fn part2(input: &Map) -> i64 {
-    let lis = LoopInformationSystem::create(input, |n| n.ends_with('A'), |n| n.ends_with('Z'));
+    let lis = input.analyze(|n| n.ends_with('A'), |n| n.ends_with('Z'));
     lis.solve().unwrap()
 }
 
@@ -334,4 +419,78 @@ XXX = (XXX, XXX)"#;
     fn test_part2_2() {
         assert_eq!(part2(&input_generator(INPUT_4)), 8);
     }
+
+    #[test]
+    fn test_to_dot() {
+        assert_eq!(
+            input_generator(INPUT_2).to_dot().to_string(),
+            r#"digraph {
+    "AAA";
+    "AAA" -> "BBB" [label="L, R"];
+    "BBB";
+    "BBB" -> "AAA" [label="L"];
+    "BBB" -> "ZZZ" [label="R"];
+    "ZZZ";
+    "ZZZ" -> "ZZZ" [label="L, R"];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_analyze() {
+        let lis = input_generator(INPUT_3).analyze(|n| n.ends_with('A'), |n| n.ends_with('Z'));
+        assert_eq!(
+            lis.infos(),
+            [
+                LoopInformation {
+                    start: "11A".to_string(),
+                    loop_entry: ("11B".to_string(), 1),
+                    loop_start: 1,
+                    statics: vec![],
+                    loop_length: 2,
+                    node_period: 2,
+                    dynamics: vec![2],
+                },
+                LoopInformation {
+                    start: "22A".to_string(),
+                    loop_entry: ("22B".to_string(), 1),
+                    loop_start: 1,
+                    statics: vec![],
+                    loop_length: 6,
+                    node_period: 3,
+                    dynamics: vec![3],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_many_hits() {
+        // every ghost runs through its own ring of 8 nodes with 4 end nodes, the cartesian product
+        // of all in-loop hits would have 4^12 combinations
+        let mut input = "L\n\n".to_string();
+        for ghost in 0..12 {
+            let node = |j: usize| {
+                let end = [0, 3, 4, 6].contains(&((j + ghost % 3) % 8));
+                format!("G{ghost:02}{}{j}", if end { 'Z' } else { 'N' })
+            };
+            input += &format!("G{ghost:02}A0 = ({}, {})\n", node(0), node(0));
+            for j in 0..8 {
+                input += &format!(
+                    "{} = ({}, {})\n",
+                    node(j),
+                    node((j + 1) % 8),
+                    node((j + 1) % 8)
+                );
+            }
+        }
+
+        let lis = input_generator(&input).analyze(|n| n.contains('A'), |n| n.contains('Z'));
+        assert_eq!(lis.solve(), None);
+
+        let lis =
+            input_generator(&input).analyze(|n| n.contains('A') && n < "G02", |n| n.contains('Z'));
+        assert_eq!(lis.solve(), Some(4));
+    }
 }
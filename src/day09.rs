@@ -1,7 +1,8 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
+use thiserror::Error;
 
-use crate::common::parse_split_whitespace;
+use crate::common::{checked_sum, parse_split_whitespace, OverflowError};
 
 #[aoc_generator(day9)]
 pub fn input_generator(input: &str) -> Vec<Vec<i32>> {
@@ -12,31 +13,85 @@ pub fn input_generator(input: &str) -> Vec<Vec<i32>> {
         .unwrap()
 }
 
-fn find_next(mut v: Vec<i32>, mut acc: i32) -> i32 {
-    let mut v2 = Vec::with_capacity(v.len());
-    loop {
-        if v.iter().all(|&n| n == 0) {
-            return acc;
+#[derive(Error, Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ExtrapolationError {
+    #[error("the differences never reach an all-zero row")]
+    NotPolynomial,
+    #[error(transparent)]
+    Overflow(#[from] OverflowError),
+}
+
+/// The polynomial through a sequence, given by the first entry of every row in its difference
+/// table.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Polynomial {
+    differences: Vec<i128>,
+}
+
+impl Polynomial {
+    pub fn fit(values: &[i32]) -> Result<Self, ExtrapolationError> {
+        let mut row: Vec<i128> = values.iter().map(|&n| n as i128).collect();
+        let mut differences = vec![];
+        loop {
+            if row.is_empty() {
+                return Err(ExtrapolationError::NotPolynomial);
+            }
+            if row.iter().all(|&n| n == 0) {
+                return Ok(Self { differences });
+            }
+
+            differences.push(row[0]);
+            row = row.iter().tuple_windows().map(|(&a, &b)| b - a).collect();
         }
+    }
 
-        acc += v.last().unwrap();
-        v2.clear();
-        v2.extend(v.iter().tuple_windows().map(|(&a, &b)| b - a));
-        std::mem::swap(&mut v, &mut v2);
+    /// `None` for the zero polynomial.
+    pub fn degree(&self) -> Option<usize> {
+        self.differences.len().checked_sub(1)
     }
+
+    /// Value at index `k`, where 0 is the first value of the sequence. This is Newton's forward
+    /// difference formula `sum(binomial(k, j) * differences[j])`, so it works for any `k`.
+    pub fn value_at(&self, k: i128) -> Result<i128, OverflowError> {
+        let mut binomial: i128 = 1;
+        let mut value: i128 = 0;
+        for (j, d) in self.differences.iter().enumerate() {
+            if j > 0 {
+                // binomial(k, j) = binomial(k, j - 1) * (k - j + 1) / j, the division is exact
+                let j = j as i128;
+                binomial = binomial
+                    .checked_mul(k.checked_sub(j - 1).ok_or(OverflowError)?)
+                    .ok_or(OverflowError)?
+                    / j;
+            }
+            value = binomial
+                .checked_mul(*d)
+                .and_then(|term| value.checked_add(term))
+                .ok_or(OverflowError)?;
+        }
+        Ok(value)
+    }
+}
+
+fn extrapolate_all(
+    input: &[Vec<i32>],
+    k: impl Fn(&[i32]) -> i128,
+) -> Result<i128, ExtrapolationError> {
+    let values = input
+        .iter()
+        .map(|s| Ok(Polynomial::fit(s)?.value_at(k(s))?))
+        .collect::<Result<Vec<_>, ExtrapolationError>>()?;
+    Ok(checked_sum(values)?)
 }
 
 #[aoc(day9, part1)]
-pub fn part1(input: &[Vec<i32>]) -> i32 {
-    input.iter().map(|s| find_next(s.to_vec(), 0)).sum()
+pub fn part1(input: &[Vec<i32>]) -> Result<i128, ExtrapolationError> {
+    extrapolate_all(input, |s| s.len() as i128)
 }
 
 #[aoc(day9, part2)]
-pub fn part2(input: &[Vec<i32>]) -> i32 {
-    input
-        .iter()
-        .map(|s| find_next(s.iter().copied().rev().collect(), 0))
-        .sum()
+pub fn part2(input: &[Vec<i32>]) -> Result<i128, ExtrapolationError> {
+    extrapolate_all(input, |_| -1)
 }
 
 #[cfg(test)]
@@ -51,11 +106,45 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&input_generator(INPUT)), 114);
+        assert_eq!(part1(&input_generator(INPUT)), Ok(114));
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&input_generator(INPUT)), 2);
+        assert_eq!(part2(&input_generator(INPUT)), Ok(2));
+    }
+
+    #[test]
+    fn test_value_at() {
+        let polynomials: Vec<_> = input_generator(INPUT)
+            .iter()
+            .map(|s| Polynomial::fit(s).unwrap())
+            .collect();
+        assert_eq!(
+            polynomials
+                .iter()
+                .map(Polynomial::degree)
+                .collect::<Vec<_>>(),
+            [Some(1), Some(2), Some(3)]
+        );
+        // triangular numbers
+        assert_eq!(polynomials[1].value_at(1_000_000), Ok(500_001_500_001));
+        assert_eq!(polynomials[1].value_at(-3), Ok(1));
+        assert_eq!(polynomials[2].value_at(-1), Ok(5));
+        assert_eq!(polynomials[1].value_at(1 << 100), Err(OverflowError));
+        assert_eq!(Polynomial::fit(&[0, 0]).unwrap().degree(), None);
+    }
+
+    #[test]
+    fn test_not_polynomial() {
+        assert_eq!(
+            Polynomial::fit(&[1, 2, 4, 8, 16]),
+            Err(ExtrapolationError::NotPolynomial)
+        );
+        assert_eq!(Polynomial::fit(&[]), Err(ExtrapolationError::NotPolynomial));
+        assert_eq!(
+            part1(&input_generator("1 2 4 8 16")),
+            Err(ExtrapolationError::NotPolynomial)
+        );
     }
 }
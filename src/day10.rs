@@ -2,7 +2,7 @@ use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
 use rustc_hash::FxHashSet;
 
-use crate::common::{Direction, Grid, Vec2i};
+use crate::common::{Direction, Grid, Rgb, Vec2i};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum Pipe {
@@ -80,23 +80,30 @@ impl Pipe {
     }
 }
 
+/// Picks the first pair of directions from the start towards compatible neighbours that closes
+/// a loop, and replaces the start with the matching pipe.
 fn replace_start(pipes: &mut Grid<Pipe>) -> Vec2i {
     let start = pipes
         .pos_iter()
         .find(|(_, pipe)| **pipe == Pipe::Start)
         .unwrap()
         .0;
-    let dirs: (Direction, Direction) = Direction::VALUES
+    let candidates: Vec<_> = Direction::VALUES
         .iter()
         .filter(|dir| {
             let offset_pos = dir.offset(&start);
             pipes.in_bounds(&offset_pos) && pipes[offset_pos].is_open(&dir.opposite())
         })
         .copied()
-        .collect_tuple()
-        .unwrap();
-    pipes[start] = Pipe::try_from(dirs).unwrap();
-    start
+        .collect();
+    for dirs in candidates.into_iter().tuple_combinations::<(_, _)>() {
+        pipes[start] = Pipe::try_from(dirs).unwrap();
+        if find_cycle(&start, pipes).is_some() {
+            return start;
+        }
+    }
+
+    panic!("assume: the start is part of a loop");
 }
 
 #[aoc_generator(day10)]
@@ -106,13 +113,20 @@ pub fn input_generator(input: &str) -> (Vec2i, Grid<Pipe>) {
     (start, pipes)
 }
 
-fn find_cycle(start: &Vec2i, pipes: &Grid<Pipe>) -> Vec<Vec2i> {
-    let mut cycle = vec![*start];
+/// Follows the pipes from `start` until they lead back to it, `None` on a dead end.
+fn find_cycle(start: &Vec2i, pipes: &Grid<Pipe>) -> Option<Vec<Vec2i>> {
+    follow_pipes(start, pipes).ok()
+}
+
+/// Follows the pipes from `start` until it gets back there, returning the cycle, or until a dead
+/// end, returning the tiles walked so far.
+fn follow_pipes(start: &Vec2i, pipes: &Grid<Pipe>) -> Result<Vec<Vec2i>, Vec<Vec2i>> {
+    let mut path = vec![*start];
     let mut came_from = Direction::North;
     loop {
-        let pos = *cycle.last().unwrap();
+        let pos = *path.last().unwrap();
         let p = &pipes[pos];
-        let dir = Direction::VALUES
+        let Some(dir) = Direction::VALUES
             .iter()
             .filter(|d| **d != came_from)
             .filter(|d| p.is_open(d))
@@ -120,29 +134,56 @@ fn find_cycle(start: &Vec2i, pipes: &Grid<Pipe>) -> Vec<Vec2i> {
                 let offset_pos = d.offset(&pos);
                 pipes.in_bounds(&offset_pos) && pipes[offset_pos].is_open(&d.opposite())
             })
-            .unwrap();
+        else {
+            return Err(path);
+        };
 
         let target_pos = dir.offset(&pos);
         if target_pos == *start {
-            break;
+            return Ok(path);
         }
 
-        cycle.push(target_pos);
+        path.push(target_pos);
         came_from = dir.opposite();
     }
+}
+
+/// Every loop of connected pipes in the grid, each starting at its first tile in reading order.
+pub fn find_loops(pipes: &Grid<Pipe>) -> Vec<Vec<Vec2i>> {
+    let mut visited = pipes.map(|_, _| false);
+    let mut loops = vec![];
+    for (pos, pipe) in pipes.pos_iter() {
+        if visited[pos] || matches!(pipe, Pipe::Ground | Pipe::Start) {
+            continue;
+        }
+
+        match follow_pipes(&pos, pipes) {
+            Ok(cycle) => {
+                for p in &cycle {
+                    visited[*p] = true;
+                }
+                loops.push(cycle);
+            }
+            // the pipes along a dead end can't be part of a loop either
+            Err(path) => {
+                for p in &path {
+                    visited[*p] = true;
+                }
+            }
+        }
+    }
 
-    cycle
+    loops
 }
 
 #[aoc(day10, part1)]
 pub fn part1((start, pipes): &(Vec2i, Grid<Pipe>)) -> usize {
-    find_cycle(start, pipes).len() / 2
+    find_cycle(start, pipes).unwrap().len() / 2
 }
 
-#[aoc(day10, part2, area_scan)]
-pub fn part2((start, pipes): &(Vec2i, Grid<Pipe>)) -> usize {
-    let cycle: FxHashSet<_> = find_cycle(start, pipes).into_iter().collect();
-    let mut inside_cycle_count = 0;
+/// Scanline classification of the tiles enclosed by the cycle.
+fn find_inside(pipes: &Grid<Pipe>, cycle: &FxHashSet<Vec2i>) -> Grid<bool> {
+    let mut inside = Grid::new_from_default(pipes.size_x, pipes.size_y);
     for y in 0..pipes.size_y {
         let mut inside_cycle = false;
         let mut cycle_opener = None;
@@ -172,17 +213,174 @@ pub fn part2((start, pipes): &(Vec2i, Grid<Pipe>)) -> usize {
                     }
                 }
             } else if inside_cycle {
-                inside_cycle_count += 1;
+                inside[pos] = true;
             }
         }
     }
 
-    inside_cycle_count
+    inside
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TileClass {
+    Loop,
+    Inside,
+    Outside,
+}
+
+impl From<TileClass> for char {
+    fn from(value: TileClass) -> Self {
+        match value {
+            TileClass::Loop => '#',
+            TileClass::Inside => 'I',
+            TileClass::Outside => 'O',
+        }
+    }
+}
+
+/// Classifies every tile relative to the given loop.
+pub fn classify(pipes: &Grid<Pipe>, cycle: &[Vec2i]) -> Grid<TileClass> {
+    let cycle: FxHashSet<_> = cycle.iter().copied().collect();
+    let inside = find_inside(pipes, &cycle);
+    pipes.map(|pos, _| {
+        if cycle.contains(&pos) {
+            TileClass::Loop
+        } else if inside[pos] {
+            TileClass::Inside
+        } else {
+            TileClass::Outside
+        }
+    })
+}
+
+/// Outside tiles that are connected without squeezing between pipes.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct OutsideRegion {
+    /// in reading order
+    pub tiles: Vec<Vec2i>,
+    /// the region can only be reached by squeezing between pipes, it doesn't touch the border
+    pub squeezed: bool,
+}
+
+/// Result of [`squeeze_flood`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Flood {
+    pub classes: Grid<TileClass>,
+    /// ordered by their first tile
+    pub outside_regions: Vec<OutsideRegion>,
+}
+
+/// Models squeezing between pipes literally: every tile becomes a `scale` x `scale` block in which
+/// the loop pipes are walls, then everything reachable from the border is flooded.
+pub fn squeeze_flood(pipes: &Grid<Pipe>, cycle: &[Vec2i], scale: usize) -> Flood {
+    assert!(
+        matches!(scale, 2 | 3),
+        "only 2x and 3x upscaling keep the gaps between pipes"
+    );
+    let center = |pos: &Vec2i| pos * scale as i64 + Vec2i::new(scale as i64 / 2, scale as i64 / 2);
+    let is_border = |pos: &Vec2i, size_x: usize, size_y: usize| {
+        pos.x == 0 || pos.y == 0 || pos.x == size_x as i64 - 1 || pos.y == size_y as i64 - 1
+    };
+
+    let mut walls = Grid::new_from_element(pipes.size_x * scale, pipes.size_y * scale, false);
+    for pos in cycle {
+        let c = center(pos);
+        walls[c] = true;
+        for dir in Direction::VALUES.iter().filter(|d| pipes[*pos].is_open(d)) {
+            let p = dir.offset(&c);
+            if walls.in_bounds(&p) {
+                walls[p] = true;
+            }
+        }
+    }
+
+    let mut q: Vec<_> = walls
+        .pos_iter()
+        .filter(|(pos, wall)| !**wall && is_border(pos, walls.size_x, walls.size_y))
+        .map(|(pos, _)| pos)
+        .collect();
+    let mut reached = walls.map(|_, _| false);
+    for pos in &q {
+        reached[*pos] = true;
+    }
+    while let Some(p) = q.pop() {
+        for n in Direction::VALUES.iter().map(|d| d.offset(&p)) {
+            if walls.in_bounds(&n) && !walls[n] && !reached[n] {
+                reached[n] = true;
+                q.push(n);
+            }
+        }
+    }
+
+    let cycle: FxHashSet<_> = cycle.iter().copied().collect();
+    let classes = pipes.map(|pos, _| {
+        if cycle.contains(&pos) {
+            TileClass::Loop
+        } else if reached[center(&pos)] {
+            TileClass::Outside
+        } else {
+            TileClass::Inside
+        }
+    });
+
+    let mut visited = classes.map(|_, c| *c != TileClass::Outside);
+    let mut outside_regions = vec![];
+    for (pos, _) in classes.pos_iter() {
+        if visited[pos] {
+            continue;
+        }
+
+        visited[pos] = true;
+        let mut tiles = vec![pos];
+        let mut q = vec![pos];
+        while let Some(p) = q.pop() {
+            for n in Direction::VALUES.iter().map(|d| d.offset(&p)) {
+                if classes.in_bounds(&n) && !visited[n] {
+                    visited[n] = true;
+                    tiles.push(n);
+                    q.push(n);
+                }
+            }
+        }
+
+        tiles.sort_unstable_by_key(|p| (p.y, p.x));
+        outside_regions.push(OutsideRegion {
+            squeezed: !tiles
+                .iter()
+                .any(|p| is_border(p, classes.size_x, classes.size_y)),
+            tiles,
+        });
+    }
+
+    Flood {
+        classes,
+        outside_regions,
+    }
+}
+
+/// Renders the loop in black and the tiles it encloses in orange.
+pub fn render_loop((start, pipes): &(Vec2i, Grid<Pipe>)) -> Grid<Rgb> {
+    let classes = classify(pipes, &find_cycle(start, pipes).unwrap());
+    pipes.map(|pos, pipe| match (classes[pos], pipe) {
+        (TileClass::Loop, _) => Rgb::BLACK,
+        (TileClass::Inside, _) => Rgb(0xff, 0x99, 0x33),
+        (TileClass::Outside, Pipe::Ground) => Rgb::WHITE,
+        // pipes that are not part of the loop
+        (TileClass::Outside, _) => Rgb(0xcc, 0xcc, 0xcc),
+    })
+}
+
+#[aoc(day10, part2, area_scan)]
+pub fn part2((start, pipes): &(Vec2i, Grid<Pipe>)) -> usize {
+    classify(pipes, &find_cycle(start, pipes).unwrap())
+        .iter()
+        .filter(|t| **t == TileClass::Inside)
+        .count()
 }
 
 #[aoc(day10, part2, picks_theorem)]
 pub fn part2_pt((start, pipes): &(Vec2i, Grid<Pipe>)) -> usize {
-    let cycle = find_cycle(start, pipes);
+    let cycle = find_cycle(start, pipes).unwrap();
     // shoelace formula to find the area of the cycle
     let double_area = cycle
         .iter()
@@ -194,6 +392,15 @@ pub fn part2_pt((start, pipes): &(Vec2i, Grid<Pipe>)) -> usize {
     (double_area + 2 - cycle.len()) / 2
 }
 
+#[aoc(day10, part2, squeeze_flood)]
+pub fn part2_flood((start, pipes): &(Vec2i, Grid<Pipe>)) -> usize {
+    squeeze_flood(pipes, &find_cycle(start, pipes).unwrap(), 3)
+        .classes
+        .iter()
+        .filter(|t| **t == TileClass::Inside)
+        .count()
+}
+
 #[cfg(test)]
 mod tests {
     use pretty_assertions::assert_eq;
@@ -305,4 +512,116 @@ L7JLJL-JLJLJL--JLJ.L"#;
     fn test_part2_pt_3() {
         assert_eq!(part2_pt(&input_generator(INPUT_7)), 10);
     }
+
+    #[test]
+    fn test_render_loop() {
+        let image = render_loop(&input_generator(INPUT_5));
+        assert_eq!(
+            image
+                .iter()
+                .filter(|c| **c == Rgb(0xff, 0x99, 0x33))
+                .count(),
+            4
+        );
+        assert_eq!(image.iter().filter(|c| **c == Rgb::BLACK).count(), 46);
+        assert_eq!(image[Vec2i::new(5, 6)], Rgb::WHITE);
+    }
+
+    #[test]
+    fn test_ambiguous_start() {
+        let (start, pipes) = input_generator(
+            r#"F-7.
+|.|.
+S-J-
+|..."#,
+        );
+        assert_eq!(pipes[start], Pipe::NorthEast);
+        assert_eq!(part1(&(start, pipes)), 4);
+    }
+
+    #[test]
+    fn test_find_loops() {
+        let (_, pipes) = input_generator(
+            r#"F7.F-7
+LJ.|.|
+S7.L-J
+LJ-7.."#,
+        );
+        let loops = find_loops(&pipes);
+        assert_eq!(loops.iter().map(Vec::len).collect::<Vec<_>>(), [4, 8, 4]);
+
+        let classes = classify(&pipes, &loops[1]);
+        assert_eq!(classes.to_string(), "OOO###\nOOO#I#\nOOO###\nOOOOOO\n");
+    }
+
+    #[test]
+    fn test_classify() {
+        let (start, pipes) = input_generator(INPUT_5);
+        let classes = classify(&pipes, &find_cycle(&start, &pipes).unwrap());
+        assert_eq!(
+            classes.to_string(),
+            r#"OOOOOOOOOOO
+O#########O
+O#########O
+O##OOOOO##O
+O##OOOOO##O
+O####O####O
+O#II#O#II#O
+O####O####O
+OOOOOOOOOOO
+"#
+        );
+    }
+
+    #[test]
+    fn test_part2_flood() {
+        for (input, expected) in [(INPUT_5, 4), (INPUT_6, 8), (INPUT_7, 10)] {
+            let input = input_generator(input);
+            assert_eq!(part2_flood(&input), expected);
+
+            let (start, pipes) = &input;
+            let cycle = find_cycle(start, pipes).unwrap();
+            for scale in [2, 3] {
+                assert_eq!(
+                    squeeze_flood(pipes, &cycle, scale).classes,
+                    classify(pipes, &cycle)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_outside_regions() {
+        let (start, pipes) = input_generator(
+            r#"..........
+.S------7.
+.|F----7|.
+.||....||.
+.||....||.
+.|L-7F-J|.
+.|..||..|.
+.L--JL--J.
+.........."#,
+        );
+        let cycle = find_cycle(&start, &pipes).unwrap();
+        for scale in [2, 3] {
+            let flood = squeeze_flood(&pipes, &cycle, scale);
+            assert_eq!(
+                flood
+                    .outside_regions
+                    .iter()
+                    .map(|r| (r.tiles[0], r.tiles.len(), r.squeezed))
+                    .collect::<Vec<_>>(),
+                [(Vec2i::new(0, 0), 34, false), (Vec2i::new(3, 3), 8, true)]
+            );
+            assert_eq!(
+                flood
+                    .classes
+                    .iter()
+                    .filter(|c| **c == TileClass::Inside)
+                    .count(),
+                4
+            );
+        }
+    }
 }
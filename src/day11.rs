@@ -1,15 +1,205 @@
+use std::collections::BinaryHeap;
 use std::str::FromStr;
 
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
+use num::BigInt;
+use thiserror::Error;
 
-use crate::common::Vec2i;
+use crate::common::{CheckedInteger, OverflowError, Vec2i};
+
+#[derive(Error, Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ExpansionError {
+    #[error("expansion factor {0} is less than 1")]
+    Factor(i64),
+    #[error(transparent)]
+    Overflow(#[from] OverflowError),
+}
 
 #[derive(Debug)]
 pub struct Universe {
     galaxies: Vec<Vec2i>,
 }
 
+impl Universe {
+    pub fn galaxies(&self) -> &[Vec2i] {
+        &self.galaxies
+    }
+
+    /// Replaces every empty column by `expansion_x` columns and every empty row by `expansion_y`
+    /// rows. Empty lines can't shrink below one line, so both factors have to be at least 1.
+    pub fn expand(
+        &self,
+        expansion_x: i64,
+        expansion_y: i64,
+    ) -> Result<ExpandedUniverse, ExpansionError> {
+        if let Some(&factor) = [expansion_x, expansion_y].iter().find(|&&e| e < 1) {
+            return Err(ExpansionError::Factor(factor));
+        }
+
+        fn expand_axis(coords: &[i64], expansion: i64) -> Result<Vec<i64>, OverflowError> {
+            let occupied: Vec<_> = coords.iter().copied().sorted_unstable().dedup().collect();
+            coords
+                .iter()
+                .map(|&c| {
+                    // every coordinate before c that isn't occupied is an empty line
+                    let empty = c - occupied.partition_point(|&o| o < c) as i64;
+                    empty
+                        .checked_mul(expansion - 1)
+                        .and_then(|e| e.checked_add(c))
+                        .ok_or(OverflowError)
+                })
+                .collect()
+        }
+
+        let xs = expand_axis(
+            &self.galaxies.iter().map(|g| g.x).collect_vec(),
+            expansion_x,
+        )?;
+        let ys = expand_axis(
+            &self.galaxies.iter().map(|g| g.y).collect_vec(),
+            expansion_y,
+        )?;
+        Ok(ExpandedUniverse::new(
+            xs.into_iter()
+                .zip(ys)
+                .map(|(x, y)| Vec2i::new(x, y))
+                .collect(),
+        ))
+    }
+}
+
+/// Galaxy coordinates after the expansion, with sorted copies of both axes for queries.
+#[derive(Debug, Clone)]
+pub struct ExpandedUniverse {
+    galaxies: Vec<Vec2i>,
+    /// galaxy indices sorted by x
+    by_x: Vec<usize>,
+    /// position of every galaxy in `by_x`
+    x_rank: Vec<usize>,
+    sorted_x: Vec<i64>,
+    sorted_y: Vec<i64>,
+    /// `prefix_x[i]` is the sum of the first `i` sorted x coordinates
+    prefix_x: Vec<i128>,
+    prefix_y: Vec<i128>,
+}
+
+impl ExpandedUniverse {
+    fn new(galaxies: Vec<Vec2i>) -> Self {
+        let by_x: Vec<_> = (0..galaxies.len())
+            .sorted_unstable_by_key(|&i| (galaxies[i].x, i))
+            .collect();
+        let mut x_rank = vec![0; galaxies.len()];
+        for (rank, &i) in by_x.iter().enumerate() {
+            x_rank[i] = rank;
+        }
+
+        let sorted_x: Vec<_> = by_x.iter().map(|&i| galaxies[i].x).collect();
+        let sorted_y: Vec<_> = galaxies.iter().map(|g| g.y).sorted_unstable().collect();
+        let prefix = |sorted: &[i64]| {
+            std::iter::once(0)
+                .chain(sorted.iter().scan(0, |sum, &c| {
+                    *sum += c as i128;
+                    Some(*sum)
+                }))
+                .collect()
+        };
+
+        Self {
+            prefix_x: prefix(&sorted_x),
+            prefix_y: prefix(&sorted_y),
+            galaxies,
+            by_x,
+            x_rank,
+            sorted_x,
+            sorted_y,
+        }
+    }
+
+    pub fn galaxies(&self) -> &[Vec2i] {
+        &self.galaxies
+    }
+
+    pub fn distance(&self, a: usize, b: usize) -> Result<i64, OverflowError> {
+        let (a, b) = (self.galaxies[a], self.galaxies[b]);
+        a.x.abs_diff(b.x)
+            .checked_add(a.y.abs_diff(b.y))
+            .and_then(|d| i64::try_from(d).ok())
+            .ok_or(OverflowError)
+    }
+
+    /// Sum of the distances from galaxy `a` to all others in `O(log n)`.
+    pub fn total_distance_from(&self, a: usize) -> i128 {
+        fn axis(sorted: &[i64], prefix: &[i128], c: i64) -> i128 {
+            let below = sorted.partition_point(|&s| s < c);
+            let above = sorted.partition_point(|&s| s <= c);
+            let c = c as i128;
+            let n = sorted.len();
+            (c * below as i128 - prefix[below])
+                + (prefix[n] - prefix[above] - c * (n - above) as i128)
+        }
+
+        let g = self.galaxies[a];
+        axis(&self.sorted_x, &self.prefix_x, g.x) + axis(&self.sorted_y, &self.prefix_y, g.y)
+    }
+
+    /// Sum of the distances between all pairs of galaxies.
+    pub fn total_distance(&self) -> i128 {
+        (0..self.galaxies.len())
+            .map(|a| self.total_distance_from(a))
+            .sum::<i128>()
+            / 2
+    }
+
+    /// The `k` galaxies closest to galaxy `a` as `(galaxy, distance)`, nearest first. The
+    /// candidates are visited in order of their x distance, which bounds the total distance, so
+    /// the search stops as soon as no closer galaxy can follow.
+    pub fn nearest(&self, a: usize, k: usize) -> Result<Vec<(usize, i64)>, OverflowError> {
+        if k == 0 {
+            return Ok(vec![]);
+        }
+
+        let x = self.galaxies[a].x;
+        let n = self.galaxies.len();
+        let center = self.x_rank[a];
+        let mut left = center.checked_sub(1);
+        let mut right = center + 1;
+        // max-heap of the best candidates so far
+        let mut best: BinaryHeap<(i64, usize)> = BinaryHeap::new();
+        loop {
+            let dl = left.map(|l| x - self.sorted_x[l]);
+            let dr = (right < n).then(|| self.sorted_x[right] - x);
+            let (rank, dx) = match (dl, dr) {
+                (None, None) => break,
+                (Some(dl), Some(dr)) if dl <= dr => (left.unwrap(), dl),
+                (Some(dl), None) => (left.unwrap(), dl),
+                (_, Some(dr)) => (right, dr),
+            };
+            if best.len() == k && best.peek().is_some_and(|&(worst, _)| dx > worst) {
+                break;
+            }
+
+            if rank < center {
+                left = rank.checked_sub(1);
+            } else {
+                right += 1;
+            }
+
+            let b = self.by_x[rank];
+            best.push((self.distance(a, b)?, b));
+            if best.len() > k {
+                best.pop();
+            }
+        }
+
+        Ok(best
+            .into_sorted_vec()
+            .into_iter()
+            .map(|(d, b)| (b, d))
+            .collect())
+    }
+}
+
 impl FromStr for Universe {
     type Err = ();
 
@@ -28,7 +218,11 @@ impl FromStr for Universe {
     }
 }
 
-fn distances(galaxies: &[Vec2i], expansion: i64) -> i64 {
+fn distances<T: CheckedInteger>(
+    galaxies: &[Vec2i],
+    expansion_x: &T,
+    expansion_y: &T,
+) -> Result<T, OverflowError> {
     // the shortest distance between two points on a grid is the manhattan distance (L1 norm)
     // there is no need for bfs/dijkstra
 
@@ -55,25 +249,37 @@ fn distances(galaxies: &[Vec2i], expansion: i64) -> i64 {
     // working out a few examples by hand and looking up the sequence in the OEIS gave the formula:
     // X(n, i) = (n - (i+1)) * (i+1)
 
-    fn component_distances(it: impl IntoIterator<Item = i64>, n: i64, exp: i64) -> i64 {
+    fn component_distances<T: CheckedInteger>(
+        it: impl IntoIterator<Item = i64>,
+        n: i64,
+        exp: &T,
+    ) -> Result<T, OverflowError> {
+        let exp_m1 = exp.try_sub(&T::one())?;
         it.into_iter()
             .tuple_windows()
-            .map(|(a, b)| b - a)
-            .map(|d| if d > 1 { d + (d - 1) * (exp - 1) } else { d })
+            .map(|(a, b)| T::try_from_i64(b - a))
+            .map(|d| {
+                let d = d?;
+                if d > T::one() {
+                    d.try_sub(&T::one())?.try_mul(&exp_m1)?.try_add(&d)
+                } else {
+                    Ok(d)
+                }
+            })
             .enumerate()
-            .map(|(i, d)| {
+            .try_fold(T::zero(), |sum, (i, d)| {
                 // formula based on https://oeis.org/A003991
                 let m = i as i64 + 1;
-                (n - m) * m * d
+                let factor = T::try_from_i64(n - m)?.try_mul(&T::try_from_i64(m)?)?;
+                sum.try_add(&factor.try_mul(&d?)?)
             })
-            .sum()
     }
 
     // the coordinates are already sorted by y, because we parse line by line
     let n = galaxies.len() as i64;
-    let x_distances = component_distances(galaxies.iter().map(|g| g.x).sorted(), n, expansion);
-    let y_distances = component_distances(galaxies.iter().map(|g| g.y), n, expansion);
-    x_distances + y_distances
+    let x_distances = component_distances(galaxies.iter().map(|g| g.x).sorted(), n, expansion_x)?;
+    let y_distances = component_distances(galaxies.iter().map(|g| g.y), n, expansion_y)?;
+    x_distances.try_add(&y_distances)
 }
 
 #[aoc_generator(day11)]
@@ -82,13 +288,23 @@ pub fn input_generator(input: &str) -> Universe {
 }
 
 #[aoc(day11, part1)]
-pub fn part1(input: &Universe) -> i64 {
-    distances(&input.galaxies, 2)
+pub fn part1(input: &Universe) -> Result<i64, OverflowError> {
+    distances(&input.galaxies, &2, &2)
+}
+
+#[aoc(day11, part1, bigint)]
+pub fn part1_bigint(input: &Universe) -> Result<BigInt, OverflowError> {
+    distances(&input.galaxies, &2.into(), &2.into())
 }
 
 #[aoc(day11, part2)]
-pub fn part2(input: &Universe) -> i64 {
-    distances(&input.galaxies, 1_000_000)
+pub fn part2(input: &Universe) -> Result<i64, OverflowError> {
+    distances(&input.galaxies, &1_000_000, &1_000_000)
+}
+
+#[aoc(day11, part2, bigint)]
+pub fn part2_bigint(input: &Universe) -> Result<BigInt, OverflowError> {
+    distances(&input.galaxies, &1_000_000.into(), &1_000_000.into())
 }
 
 #[cfg(test)]
@@ -112,22 +328,103 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&input_generator(INPUT)), 374);
+        assert_eq!(part1(&input_generator(INPUT)), Ok(374));
     }
 
     #[test]
     fn test_part2_partial() {
         let input = input_generator(INPUT);
-        assert_eq!(distances(&input.galaxies, 100), 8410);
+        assert_eq!(distances(&input.galaxies, &100i64, &100), Ok(8410));
     }
 
     #[test]
     fn test_part1_2() {
-        assert_eq!(part1(&input_generator(INPUT_2)), 2466269413);
+        assert_eq!(part1(&input_generator(INPUT_2)), Ok(2466269413));
     }
 
     #[test]
     fn test_part2_2() {
-        assert_eq!(part2(&input_generator(INPUT_2)), 155354715564293);
+        assert_eq!(part2(&input_generator(INPUT_2)), Ok(155354715564293));
+    }
+
+    #[test]
+    fn test_overflow() {
+        let input = input_generator(INPUT_2);
+        assert_eq!(
+            distances(&input.galaxies, &1_000_000_000_000i64, &1_000_000_000_000),
+            Err(OverflowError)
+        );
+        assert_eq!(
+            distances(
+                &input.galaxies,
+                &BigInt::from(1_000_000_000_000i64),
+                &BigInt::from(1_000_000_000_000i64)
+            ),
+            Ok("155352560002155564293".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_expand() {
+        let universe = input_generator(INPUT);
+        let expanded = universe.expand(2, 2).unwrap();
+        assert_eq!(expanded.galaxies()[0], Vec2i::new(4, 0));
+        assert_eq!(expanded.galaxies()[8], Vec2i::new(5, 11));
+        // galaxies 5 and 9 from the puzzle description
+        assert_eq!(expanded.distance(4, 8), Ok(9));
+        assert_eq!(expanded.total_distance(), 374);
+
+        let stretched = universe.expand(1, 10).unwrap();
+        assert_eq!(stretched.galaxies()[8], Vec2i::new(4, 27));
+        assert_eq!(
+            stretched.total_distance(),
+            distances(&universe.galaxies, &1i128, &10).unwrap()
+        );
+
+        assert_eq!(universe.expand(0, 2).err(), Some(ExpansionError::Factor(0)));
+        assert_eq!(
+            universe.expand(2, -1).err(),
+            Some(ExpansionError::Factor(-1))
+        );
+        // the coordinates still fit, but the distance between galaxies 6 and 8 doesn't
+        let huge = universe.expand(i64::MAX / 4, i64::MAX / 4).unwrap();
+        assert_eq!(huge.distance(5, 7), Err(OverflowError));
+        assert!(huge.distance(5, 6).is_ok());
+    }
+
+    #[test]
+    fn test_alternative_input() {
+        let universe = input_generator(INPUT_2);
+        for (expansion_x, expansion_y) in [(2, 2), (1_000_000, 1_000_000), (3, 7)] {
+            let expanded = universe.expand(expansion_x, expansion_y).unwrap();
+            assert_eq!(
+                Ok(expanded.total_distance()),
+                distances(
+                    &universe.galaxies,
+                    &(expansion_x as i128),
+                    &(expansion_y as i128)
+                )
+            );
+        }
+        assert_eq!(
+            universe
+                .expand(1_000_000, 1_000_000)
+                .unwrap()
+                .total_distance(),
+            155354715564293
+        );
+
+        let expanded = universe.expand(3, 7).unwrap();
+        let n = expanded.galaxies().len();
+        for a in [0, n / 2, n - 1] {
+            let brute_force: Vec<_> = (0..n)
+                .filter(|&b| b != a)
+                .map(|b| (b, expanded.distance(a, b).unwrap()))
+                .sorted_by_key(|&(b, d)| (d, b))
+                .collect();
+            let total: i64 = brute_force.iter().map(|(_, d)| d).sum();
+            assert_eq!(expanded.total_distance_from(a), total as i128);
+            assert_eq!(expanded.nearest(a, 10).unwrap(), brute_force[..10]);
+        }
     }
 }
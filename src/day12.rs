@@ -1,12 +1,16 @@
+use std::collections::VecDeque;
 use std::str::FromStr;
 
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
 use nalgebra::DMatrix;
+use num::{BigUint, One, Zero};
+use rustc_hash::{FxHashMap, FxHashSet};
+use thiserror::Error;
 
-use crate::common::{parse_lines, parse_split};
+use crate::common::{checked_sum, parse_lines, parse_split, CheckedInteger, OverflowError};
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum Spring {
     Dot,
     Hash,
@@ -28,6 +32,16 @@ impl TryFrom<char> for Spring {
     }
 }
 
+impl From<Spring> for char {
+    fn from(value: Spring) -> Self {
+        match value {
+            Spring::Dot => '.',
+            Spring::Hash => '#',
+            Spring::Question => '?',
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Springs {
     springs: Vec<Spring>,
@@ -50,7 +64,7 @@ impl FromStr for Springs {
 }
 
 impl Springs {
-    fn unfold(&self, n: usize) -> Springs {
+    pub fn unfold(&self, n: usize) -> Springs {
         let mut springs = Vec::with_capacity(if n == 0 {
             0
         } else {
@@ -75,74 +89,447 @@ pub fn input_generator(input: &str) -> Vec<Springs> {
     parse_lines(input).unwrap()
 }
 
-fn count_alignments(springs: &Springs) -> usize {
-    // dynamic programming solution
-    // taking inspiration from DNA alignment matching
+#[derive(Debug, Copy, Clone)]
+enum PatternChar {
+    DotStar,
+    Dot,
+    Hash,
+}
+
+/// The dynamic programming table behind [`count_alignments`], which also drives enumerating and
+/// sampling the arrangements.
+#[derive(Debug)]
+pub struct Alignments<T> {
+    springs: Vec<Spring>,
+    pattern: Vec<PatternChar>,
+    /// `table[(r, c)]` counts the ways to align `pattern[r..]` with `springs[c..]`
+    table: DMatrix<T>,
+}
 
-    #[derive(Copy, Clone)]
-    enum PatternChar {
-        DotStar,
-        Dot,
-        Hash,
-    }
+impl<T: CheckedInteger + 'static> Alignments<T> {
+    pub fn new(springs: &Springs) -> Result<Self, OverflowError> {
+        // dynamic programming solution
+        // taking inspiration from DNA alignment matching
 
-    let mut pattern = vec![PatternChar::DotStar];
-    let mut first = true;
-    for n in &springs.amounts {
-        if first {
-            first = false;
-        } else {
-            pattern.extend([PatternChar::Dot, PatternChar::DotStar]);
+        let mut pattern = vec![PatternChar::DotStar];
+        let mut first = true;
+        for n in &springs.amounts {
+            if first {
+                first = false;
+            } else {
+                pattern.extend([PatternChar::Dot, PatternChar::DotStar]);
+            }
+            for _ in 0..*n {
+                pattern.push(PatternChar::Hash);
+            }
         }
-        for _ in 0..*n {
-            pattern.push(PatternChar::Hash);
+        pattern.push(PatternChar::DotStar);
+
+        let pl = pattern.len();
+        let hl = springs.springs.len();
+        let mut m = DMatrix::from_element(pl + 1, hl + 1, T::zero());
+
+        for r in (0..=pl).rev() {
+            for c in (0..=hl).rev() {
+                if r == pl && c == hl {
+                    m[(r, c)] = T::one();
+                } else if c == hl {
+                    m[(r, c)] = match pattern[r] {
+                        PatternChar::DotStar => m[(r + 1, c)].clone(),
+                        _ => T::zero(),
+                    }
+                } else if r == pl {
+                    m[(r, c)] = T::zero()
+                } else {
+                    m[(r, c)] = match (pattern[r], springs.springs[c]) {
+                        (PatternChar::DotStar, Spring::Dot) => m[(r, c + 1)].clone(),
+                        (PatternChar::DotStar, Spring::Hash) => m[(r + 1, c)].clone(),
+                        (PatternChar::DotStar, Spring::Question) => {
+                            m[(r, c + 1)].try_add(&m[(r + 1, c)])?
+                        }
+                        (PatternChar::Dot, Spring::Dot | Spring::Question)
+                        | (PatternChar::Hash, Spring::Hash | Spring::Question) => {
+                            m[(r + 1, c + 1)].clone()
+                        }
+                        _ => T::zero(),
+                    }
+                }
+            }
         }
+
+        Ok(Alignments {
+            springs: springs.springs.clone(),
+            pattern,
+            table: m,
+        })
     }
-    pattern.push(PatternChar::DotStar);
 
-    let pl = pattern.len();
-    let hl = springs.springs.len();
-    let mut m = DMatrix::from_element(pl + 1, hl + 1, 0);
+    pub fn count(&self) -> &T {
+        &self.table[(0, 0)]
+    }
 
-    for r in (0..=pl).rev() {
-        for c in (0..=hl).rev() {
-            if r == pl && c == hl {
-                m[(r, c)] = 1;
-            } else if c == hl {
-                m[(r, c)] = match pattern[r] {
-                    PatternChar::DotStar => m[(r + 1, c)],
-                    _ => 0,
+    /// Follows the table from the start, calling `choose(dots, total)` whenever an unknown spring
+    /// can be either: `dots` of the `total` remaining arrangements continue with an operational
+    /// spring, and `choose` returns whether to take a damaged one instead.
+    ///
+    /// Every state on the path has at least one arrangement, so this never gets stuck.
+    fn walk(&self, mut choose: impl FnMut(&T, &T) -> bool) -> Vec<Spring> {
+        let (mut r, mut c) = (0, 0);
+        let mut arrangement = Vec::with_capacity(self.springs.len());
+        while c < self.springs.len() {
+            match (self.pattern[r], self.springs[c]) {
+                (PatternChar::DotStar, Spring::Dot) => {
+                    arrangement.push(Spring::Dot);
+                    c += 1;
+                }
+                (PatternChar::DotStar, Spring::Hash) => r += 1,
+                (PatternChar::DotStar, Spring::Question) => {
+                    let dots = &self.table[(r, c + 1)];
+                    let hashes = &self.table[(r + 1, c)];
+                    if hashes.is_zero() || (!dots.is_zero() && !choose(dots, &self.table[(r, c)])) {
+                        arrangement.push(Spring::Dot);
+                        c += 1;
+                    } else {
+                        r += 1;
+                    }
+                }
+                (PatternChar::Dot, _) => {
+                    arrangement.push(Spring::Dot);
+                    r += 1;
+                    c += 1;
                 }
-            } else if r == pl {
-                m[(r, c)] = 0
+                (PatternChar::Hash, _) => {
+                    arrangement.push(Spring::Hash);
+                    r += 1;
+                    c += 1;
+                }
+            }
+        }
+
+        arrangement
+    }
+
+    /// The arrangement at position `rank` when all of them are ordered lexicographically with
+    /// operational before damaged springs.
+    pub fn unrank(&self, rank: &T) -> Option<Vec<Spring>> {
+        if rank >= self.count() {
+            return None;
+        }
+
+        let mut rank = rank.clone();
+        Some(self.walk(|dots, _| {
+            if &rank < dots {
+                false
             } else {
-                m[(r, c)] = match (pattern[r], springs.springs[c]) {
-                    (PatternChar::DotStar, Spring::Dot) => m[(r, c + 1)],
-                    (PatternChar::DotStar, Spring::Hash) => m[(r + 1, c)],
-                    (PatternChar::DotStar, Spring::Question) => m[(r, c + 1)] + m[(r + 1, c)],
-                    (PatternChar::Dot, Spring::Dot | Spring::Question)
-                    | (PatternChar::Hash, Spring::Hash | Spring::Question) => m[(r + 1, c + 1)],
-                    _ => 0,
+                rank = rank.try_sub(dots).unwrap();
+                true
+            }
+        }))
+    }
+
+    /// All arrangements in the order of [`Alignments::unrank`].
+    pub fn iter(&self) -> Arrangements<'_, T> {
+        Arrangements {
+            alignments: self,
+            next: Some(T::zero()),
+        }
+    }
+
+    /// Picks one of the arrangements uniformly at random. `random_bit` has to return independent
+    /// fair coin flips.
+    ///
+    /// Every choice takes an operational spring with probability `dots / total` by comparing the
+    /// binary expansion of that fraction with random bits until they differ, so the result is
+    /// exactly uniform no matter how large the counts are.
+    pub fn sample(&self, mut random_bit: impl FnMut() -> bool) -> Option<Vec<Spring>> {
+        if self.count().is_zero() {
+            return None;
+        }
+
+        Some(self.walk(|dots, total| {
+            let mut remainder = dots.clone();
+            loop {
+                // next bit of remainder / total, without computing 2 * remainder
+                let rest = total.try_sub(&remainder).unwrap();
+                let bit = remainder >= rest;
+                if random_bit() != bit {
+                    // the random fraction is below dots / total iff it has the smaller bit
+                    return !bit;
                 }
+
+                remainder = if bit {
+                    remainder.try_sub(&rest).unwrap()
+                } else {
+                    remainder.try_add(&remainder).unwrap()
+                };
             }
+        }))
+    }
+}
+
+/// Iterator over the arrangements of a row, see [`Alignments::iter`].
+#[derive(Debug)]
+pub struct Arrangements<'a, T> {
+    alignments: &'a Alignments<T>,
+    next: Option<T>,
+}
+
+impl<T: CheckedInteger + 'static> Iterator for Arrangements<'_, T> {
+    type Item = Vec<Spring>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rank = self.next.take()?;
+        let arrangement = self.alignments.unrank(&rank)?;
+        self.next = rank.try_add(&T::one()).ok();
+        Some(arrangement)
+    }
+}
+
+#[derive(Error, Debug, Copy, Clone, Eq, PartialEq)]
+pub enum UnfoldError {
+    #[error("copies of the row don't always hold the same number of groups")]
+    UnboundedDrift,
+}
+
+/// A state at the boundary between two copies of an unfolded row: the group being matched
+/// relative to the groups all previous copies would hold on their own, and how many of its
+/// damaged springs are already placed (0 when between groups).
+type BoundaryState = (i64, u32);
+
+type Transitions = Vec<(BoundaryState, BigUint)>;
+
+/// Counts the arrangements of `unfold(n)` without building the unfolded row.
+///
+/// Each copy after the first one adds a `?` and the springs, which is a linear map on the counts
+/// per [`BoundaryState`]. When the relative group index of the useful states stays bounded, this
+/// map is a finite matrix and its powers come from repeated squaring. Otherwise the counts
+/// aren't a linear recurrence at all: `?? 1` has `binomial(2n, n)` arrangements.
+#[derive(Debug, Clone)]
+pub struct Transfer {
+    /// counts after the first copy
+    initial: Vec<BigUint>,
+    /// `block[i][j]` counts the ways one more copy leads from state `i` to state `j`
+    block: Vec<Vec<BigUint>>,
+    accepting: Vec<bool>,
+}
+
+impl Springs {
+    pub fn transfer(&self) -> Result<Transfer, UnfoldError> {
+        let amounts = &self.amounts;
+        let g = amounts.len();
+        let group = |t: usize| if g == 0 { 0 } else { amounts[t % g] };
+
+        // runs the automaton over the springs, starting with t groups done and k springs placed
+        let run = |springs: &[Spring], start: (usize, u32)| {
+            let mut states = FxHashMap::default();
+            states.insert(start, BigUint::one());
+            for spring in springs {
+                let mut next: FxHashMap<(usize, u32), BigUint> = FxHashMap::default();
+                for ((t, k), n) in states {
+                    if matches!(spring, Spring::Dot | Spring::Question) {
+                        if k == 0 {
+                            *next.entry((t, 0)).or_default() += &n;
+                        } else if k == group(t) {
+                            *next.entry((t + 1, 0)).or_default() += &n;
+                        }
+                    }
+                    if matches!(spring, Spring::Hash | Spring::Question) && k < group(t) {
+                        *next.entry((t, k + 1)).or_default() += n;
+                    }
+                }
+                states = next;
+            }
+            states
+        };
+
+        let g = g as i64;
+        let first = run(&self.springs, (0, 0))
+            .into_iter()
+            .map(|((t, k), n)| ((t as i64 - g, k), n))
+            .collect_vec();
+
+        let mut block_springs = vec![Spring::Question];
+        block_springs.extend_from_slice(&self.springs);
+        // transitions only depend on the group index modulo g
+        let mut transitions: FxHashMap<(usize, u32), Transitions> = FxHashMap::default();
+        let mut successors = |(d, k): BoundaryState| {
+            let r = if g == 0 { 0 } else { d.rem_euclid(g) as usize };
+            transitions
+                .entry((r, k))
+                .or_insert_with(|| {
+                    run(&block_springs, (r, k))
+                        .into_iter()
+                        .map(|((t, k), n)| ((t as i64 - r as i64 - g, k), n))
+                        .collect()
+                })
+                .iter()
+                .map(|((delta, k), n)| ((d + delta, *k), n.clone()))
+                .collect_vec()
+        };
+
+        let mut accepting = vec![(0, 0)];
+        if g > 0 {
+            accepting.push((-1, group(g as usize - 1)));
         }
+
+        // a copy can't place more groups than it has springs, so with a bounded drift all useful
+        // states are within a few copies worth of groups around zero
+        let quotient_states = (amounts.iter().sum::<u32>() as i64 + g + 1) as usize;
+        let per_copy = self.springs.len() as i64 + 2;
+        let cap = 2 * quotient_states as i64 * per_copy + 2 * g;
+
+        let mut reachable: FxHashSet<BoundaryState> = FxHashSet::default();
+        let mut edges: FxHashMap<BoundaryState, Transitions> = FxHashMap::default();
+        let mut queue: VecDeque<_> = first.iter().map(|(s, _)| *s).collect();
+        while let Some(state) = queue.pop_front() {
+            if state.0.abs() > cap || !reachable.insert(state) {
+                continue;
+            }
+            let next = successors(state);
+            queue.extend(next.iter().map(|(s, _)| *s));
+            edges.insert(state, next);
+        }
+
+        let mut predecessors: FxHashMap<BoundaryState, Vec<BoundaryState>> = FxHashMap::default();
+        for (&from, next) in &edges {
+            for (to, _) in next {
+                predecessors.entry(*to).or_default().push(from);
+            }
+        }
+        let mut useful: FxHashSet<BoundaryState> = FxHashSet::default();
+        let mut queue: VecDeque<_> = accepting
+            .iter()
+            .copied()
+            .filter(|s| reachable.contains(s))
+            .collect();
+        while let Some(state) = queue.pop_front() {
+            if useful.insert(state) {
+                queue.extend(predecessors.get(&state).into_iter().flatten());
+            }
+        }
+
+        if useful.iter().any(|(d, _)| d.abs() > cap - per_copy) {
+            return Err(UnfoldError::UnboundedDrift);
+        }
+
+        let states = useful.into_iter().sorted_unstable().collect_vec();
+        let index: FxHashMap<_, _> = states.iter().enumerate().map(|(i, s)| (*s, i)).collect();
+        let mut initial = vec![BigUint::zero(); states.len()];
+        for (state, n) in first {
+            if let Some(&i) = index.get(&state) {
+                initial[i] += n;
+            }
+        }
+        let mut block = vec![vec![BigUint::zero(); states.len()]; states.len()];
+        for (i, state) in states.iter().enumerate() {
+            for (to, n) in &edges[state] {
+                if let Some(&j) = index.get(to) {
+                    block[i][j] += n;
+                }
+            }
+        }
+
+        Ok(Transfer {
+            initial,
+            block,
+            accepting: states.iter().map(|s| accepting.contains(s)).collect(),
+        })
     }
+}
 
-    m[(0, 0)]
+impl Transfer {
+    /// Number of states left after pruning the ones that can't lead to an arrangement.
+    pub fn states(&self) -> usize {
+        self.initial.len()
+    }
+
+    fn power(&self, n: u64, reduce: impl Fn(BigUint) -> BigUint) -> BigUint {
+        if n == 0 {
+            // the empty row has exactly one arrangement
+            return reduce(BigUint::one());
+        }
+
+        let mul = |v: &[BigUint], m: &[Vec<BigUint>]| {
+            (0..m.len())
+                .map(|j| reduce(v.iter().zip(m).map(|(x, row)| x * &row[j]).sum()))
+                .collect_vec()
+        };
+
+        let mut vector = self.initial.iter().cloned().map(&reduce).collect_vec();
+        let mut matrix = self
+            .block
+            .iter()
+            .map(|row| row.iter().cloned().map(&reduce).collect_vec())
+            .collect_vec();
+        let mut e = n - 1;
+        while e > 0 {
+            if e & 1 == 1 {
+                vector = mul(&vector, &matrix);
+            }
+            e >>= 1;
+            if e > 0 {
+                matrix = matrix.iter().map(|row| mul(row, &matrix)).collect();
+            }
+        }
+
+        reduce(
+            vector
+                .into_iter()
+                .zip(&self.accepting)
+                .filter(|(_, &a)| a)
+                .map(|(n, _)| n)
+                .sum(),
+        )
+    }
+
+    /// Number of arrangements of `unfold(n)`.
+    pub fn count(&self, n: u64) -> BigUint {
+        self.power(n, |x| x)
+    }
+
+    /// Number of arrangements of `unfold(n)` modulo `modulus`, which keeps the numbers small even
+    /// for billions of copies.
+    pub fn count_mod(&self, n: u64, modulus: u64) -> u64 {
+        assert!(modulus > 0, "assume: positive modulus");
+        let modulus = BigUint::from(modulus);
+        self.power(n, |x| x % &modulus).try_into().unwrap()
+    }
+}
+
+fn count_alignments<T: CheckedInteger + 'static>(springs: &Springs) -> Result<T, OverflowError> {
+    Ok(Alignments::<T>::new(springs)?.count().clone())
 }
 
 #[aoc(day12, part1)]
-pub fn part1(input: &[Springs]) -> usize {
-    input.iter().map(count_alignments).sum()
+pub fn part1(input: &[Springs]) -> Result<usize, OverflowError> {
+    input
+        .iter()
+        .map(count_alignments)
+        .process_results(|it| checked_sum(it))?
+}
+
+#[aoc(day12, part1, bigint)]
+pub fn part1_bigint(input: &[Springs]) -> Result<BigUint, OverflowError> {
+    input
+        .iter()
+        .map(count_alignments)
+        .process_results(|it| checked_sum(it))?
 }
 
 #[aoc(day12, part2)]
-pub fn part2(input: &[Springs]) -> usize {
+pub fn part2(input: &[Springs]) -> Result<usize, OverflowError> {
     input
         .iter()
-        .map(|s| s.unfold(5))
-        .map(|s| count_alignments(&s))
-        .sum()
+        .map(|s| count_alignments(&s.unfold(5)))
+        .process_results(|it| checked_sum(it))?
+}
+
+#[aoc(day12, part2, bigint)]
+pub fn part2_bigint(input: &[Springs]) -> Result<BigUint, OverflowError> {
+    input
+        .iter()
+        .map(|s| count_alignments(&s.unfold(5)))
+        .process_results(|it| checked_sum(it))?
 }
 
 #[cfg(test)]
@@ -150,6 +537,7 @@ mod tests {
     use pretty_assertions::assert_eq;
 
     use super::*;
+    use crate::common::XorShift;
 
     const INPUT_1: &str = r#"#.#.### 1,1,3
 .#...#....###. 1,1,3
@@ -167,16 +555,133 @@ mod tests {
 
     #[test]
     fn test_part1_1() {
-        assert_eq!(part1(&input_generator(INPUT_1)), 6);
+        assert_eq!(part1(&input_generator(INPUT_1)), Ok(6));
     }
 
     #[test]
     fn test_part1_2() {
-        assert_eq!(part1(&input_generator(INPUT_2)), 21);
+        assert_eq!(part1(&input_generator(INPUT_2)), Ok(21));
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&input_generator(INPUT_2)), 525152);
+        assert_eq!(part2(&input_generator(INPUT_2)), Ok(525152));
+    }
+
+    #[test]
+    fn test_overflow() {
+        // 40 groups of one spring in 119 cells: binomial(80, 40) arrangements
+        let springs = input_generator("?? 1").remove(0).unfold(40);
+        assert_eq!(count_alignments::<u64>(&springs), Err(OverflowError));
+        assert_eq!(
+            count_alignments::<BigUint>(&springs),
+            Ok("107507208733336176461620".parse().unwrap())
+        );
+    }
+
+    fn render(arrangement: &[Spring]) -> String {
+        arrangement.iter().map(|&s| char::from(s)).collect()
+    }
+
+    fn is_valid(springs: &Springs, arrangement: &[Spring]) -> bool {
+        let fits = springs.springs.iter().zip(arrangement).all(|(s, a)| {
+            matches!(
+                (s, a),
+                (Spring::Question, _) | (Spring::Dot, Spring::Dot) | (Spring::Hash, Spring::Hash)
+            )
+        });
+        let groups = render(arrangement)
+            .split('.')
+            .filter(|g| !g.is_empty())
+            .map(|g| g.len() as u32)
+            .collect_vec();
+        fits && groups == springs.amounts
+    }
+
+    #[test]
+    fn test_arrangements() {
+        let springs = input_generator("?###???????? 3,2,1").remove(0);
+        let alignments = Alignments::<u64>::new(&springs).unwrap();
+        let arrangements = alignments.iter().collect_vec();
+        assert_eq!(arrangements.len(), 10);
+        assert_eq!(render(&arrangements[0]), ".###....##.#");
+        assert_eq!(render(&arrangements[9]), ".###.##.#...");
+        assert!(arrangements.iter().all(|a| is_valid(&springs, a)));
+        assert!(arrangements
+            .iter()
+            .map(|a| render(a))
+            .tuple_windows()
+            .all(|(a, b)| a.replace('#', "~") < b.replace('#', "~")));
+        assert_eq!(alignments.unrank(&10), None);
+
+        let unfolded = springs.unfold(5);
+        let alignments = Alignments::<BigUint>::new(&unfolded).unwrap();
+        assert_eq!(alignments.count(), &BigUint::from(506250u32));
+        let last = alignments.unrank(&BigUint::from(506249u32)).unwrap();
+        assert!(is_valid(&unfolded, &last));
+        assert_eq!(
+            alignments.iter().nth(1234).as_ref(),
+            alignments.unrank(&BigUint::from(1234u32)).as_ref()
+        );
+    }
+
+    #[test]
+    fn test_sample() {
+        let springs = input_generator(".??..??...?##. 1,1,3").remove(0);
+        let alignments = Alignments::<u64>::new(&springs).unwrap();
+
+        let mut rng = XorShift::default();
+        let mut random_bit = || rng.bit();
+
+        let mut seen = FxHashMap::default();
+        for _ in 0..4000 {
+            let sample = alignments.sample(&mut random_bit).unwrap();
+            assert!(is_valid(&springs, &sample));
+            *seen.entry(render(&sample)).or_insert(0) += 1;
+        }
+        assert_eq!(seen.len(), 4);
+        assert!(seen.values().all(|&n| (800..1200).contains(&n)));
+
+        let impossible = input_generator("#.? 2").remove(0);
+        assert_eq!(
+            Alignments::<u64>::new(&impossible)
+                .unwrap()
+                .sample(random_bit),
+            None
+        );
+    }
+
+    #[test]
+    fn test_transfer() {
+        for springs in input_generator(INPUT_2) {
+            let transfer = springs.transfer().unwrap();
+            assert_eq!(transfer.count(0), BigUint::one());
+            for n in 1..=5 {
+                assert_eq!(
+                    transfer.count(n as u64),
+                    count_alignments::<BigUint>(&springs.unfold(n)).unwrap()
+                );
+            }
+        }
+
+        // every copy holds all three groups, which leaves 15 arrangements for each copy but the
+        // first one
+        let springs = input_generator("?###???????? 3,2,1").remove(0);
+        let transfer = springs.transfer().unwrap();
+        assert_eq!(
+            transfer.count(1000),
+            BigUint::from(10u32) * BigUint::from(15u32).pow(999)
+        );
+        let p = 1_000_000_007u64;
+        let n = 1_000_000_000_000u64;
+        let expected = BigUint::from(10u32)
+            * BigUint::from(15u32).modpow(&BigUint::from(n - 1), &BigUint::from(p))
+            % p;
+        assert_eq!(BigUint::from(transfer.count_mod(n, p)), expected);
+
+        assert_eq!(
+            input_generator("?? 1").remove(0).transfer().unwrap_err(),
+            UnfoldError::UnboundedDrift
+        );
     }
 }
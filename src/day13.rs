@@ -1,4 +1,5 @@
 use aoc_runner_derive::{aoc, aoc_generator};
+use thiserror::Error;
 
 use crate::common::{Grid, Vec2i};
 
@@ -22,44 +23,115 @@ impl TryFrom<char> for Ground {
     }
 }
 
-fn find_reflection(grid: &Grid<Ground>, smudges: usize) -> usize {
-    'outer: for mirror_x in 1..grid.size_x {
-        let mut smudges_found = 0;
+/// A mirror between two rows or columns, given by the index of the row or column after it.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Axis {
+    Vertical(usize),
+    Horizontal(usize),
+}
+
+impl Axis {
+    pub fn summary(self) -> usize {
+        match self {
+            Axis::Vertical(x) => x,
+            Axis::Horizontal(y) => 100 * y,
+        }
+    }
+}
+
+/// An axis together with the pairs of mirrored cells that differ. Flipping one cell of every
+/// pair makes the pattern symmetric.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Mirror {
+    pub axis: Axis,
+    pub smudges: Vec<(Vec2i, Vec2i)>,
+}
+
+impl Mirror {
+    pub fn mismatches(&self) -> usize {
+        self.smudges.len()
+    }
+}
+
+#[derive(Error, Debug, Copy, Clone, Eq, PartialEq)]
+#[error("no axis with exactly {smudges} smudges")]
+pub struct NoReflection {
+    pub smudges: usize,
+}
+
+/// Every possible mirror of a pattern, vertical ones first.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Reflection {
+    mirrors: Vec<Mirror>,
+}
+
+impl Reflection {
+    pub fn new(grid: &Grid<Ground>) -> Self {
+        // every row and column is a bitmask split into 64 bit words
+        let mut rows = vec![vec![0u64; grid.size_x.div_ceil(64)]; grid.size_y];
+        let mut columns = vec![vec![0u64; grid.size_y.div_ceil(64)]; grid.size_x];
         for y in 0..grid.size_y {
-            for (x, mirrored_x) in (0..mirror_x).rev().zip(mirror_x..grid.size_x) {
-                if grid[Vec2i::new(x as _, y as _)] != grid[Vec2i::new(mirrored_x as _, y as _)] {
-                    smudges_found += 1;
-                    if smudges_found > smudges {
-                        continue 'outer;
-                    }
+            for x in 0..grid.size_x {
+                if grid[Vec2i::new(x as _, y as _)] == Ground::Hash {
+                    rows[y][x / 64] |= 1 << (x % 64);
+                    columns[x][y / 64] |= 1 << (y % 64);
                 }
             }
         }
 
-        if smudges_found == smudges {
-            return mirror_x;
-        }
-    }
-
-    'outer: for mirror_y in 1..grid.size_y {
-        let mut smudges_found = 0;
-        for x in 0..grid.size_x {
-            for (y, mirrored_y) in (0..mirror_y).rev().zip(mirror_y..grid.size_y) {
-                if grid[Vec2i::new(x as _, y as _)] != grid[Vec2i::new(x as _, mirrored_y as _)] {
-                    smudges_found += 1;
-                    if smudges_found > smudges {
-                        continue 'outer;
+        /// Mirrors every line before `mirror` onto the ones after it and reports the differing
+        /// bits as `(line, bit)` pairs.
+        fn smudges(lines: &[Vec<u64>], mirror: usize) -> Vec<((usize, usize), (usize, usize))> {
+            let mut smudges = vec![];
+            for (line, mirrored) in (0..mirror).rev().zip(mirror..lines.len()) {
+                for (word, (a, b)) in lines[line].iter().zip(&lines[mirrored]).enumerate() {
+                    let mut diff = a ^ b;
+                    while diff != 0 {
+                        let bit = 64 * word + diff.trailing_zeros() as usize;
+                        smudges.push(((line, bit), (mirrored, bit)));
+                        diff &= diff - 1;
                     }
                 }
             }
+            smudges
         }
 
-        if smudges_found == smudges {
-            return 100 * mirror_y;
+        let to_vec = |(x, y): (usize, usize)| Vec2i::new(x as _, y as _);
+        let vertical = (1..grid.size_x).map(|x| Mirror {
+            axis: Axis::Vertical(x),
+            smudges: smudges(&columns, x)
+                .into_iter()
+                .map(|(a, b)| (to_vec(a), to_vec(b)))
+                .collect(),
+        });
+        let horizontal = (1..grid.size_y).map(|y| Mirror {
+            axis: Axis::Horizontal(y),
+            smudges: smudges(&rows, y)
+                .into_iter()
+                .map(|((y0, x0), (y1, x1))| (to_vec((x0, y0)), to_vec((x1, y1))))
+                .collect(),
+        });
+
+        Reflection {
+            mirrors: vertical.chain(horizontal).collect(),
         }
     }
 
-    unreachable!();
+    pub fn mirrors(&self) -> &[Mirror] {
+        &self.mirrors
+    }
+
+    /// The first mirror that needs exactly `smudges` cells flipped.
+    pub fn find(&self, smudges: usize) -> Result<&Mirror, NoReflection> {
+        self.mirrors
+            .iter()
+            .find(|m| m.mismatches() == smudges)
+            .ok_or(NoReflection { smudges })
+    }
+}
+
+fn find_reflection(grid: &Grid<Ground>, smudges: usize) -> Result<usize, NoReflection> {
+    Ok(Reflection::new(grid).find(smudges)?.axis.summary())
 }
 
 #[aoc_generator(day13)]
@@ -72,12 +144,12 @@ pub fn input_generator(input: &str) -> Vec<Grid<Ground>> {
 }
 
 #[aoc(day13, part1)]
-pub fn part1(input: &[Grid<Ground>]) -> usize {
+pub fn part1(input: &[Grid<Ground>]) -> Result<usize, NoReflection> {
     input.iter().map(|p| find_reflection(p, 0)).sum()
 }
 
 #[aoc(day13, part2)]
-pub fn part2(input: &[Grid<Ground>]) -> usize {
+pub fn part2(input: &[Grid<Ground>]) -> Result<usize, NoReflection> {
     input.iter().map(|p| find_reflection(p, 1)).sum()
 }
 
@@ -105,11 +177,90 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&input_generator(INPUT)), 405);
+        assert_eq!(part1(&input_generator(INPUT)), Ok(405));
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&input_generator(INPUT)), 400);
+        assert_eq!(part2(&input_generator(INPUT)), Ok(400));
+    }
+
+    #[test]
+    fn test_reflection() {
+        let patterns = input_generator(INPUT);
+        let reflection = Reflection::new(&patterns[0]);
+        assert_eq!(reflection.mirrors().len(), 8 + 6);
+        assert_eq!(reflection.find(0).unwrap().axis, Axis::Vertical(5));
+        assert_eq!(
+            reflection.find(1).unwrap(),
+            &Mirror {
+                axis: Axis::Horizontal(3),
+                smudges: vec![(Vec2i::new(0, 0), Vec2i::new(0, 5))],
+            }
+        );
+
+        let reflection = Reflection::new(&patterns[1]);
+        assert_eq!(
+            reflection.find(1).unwrap().smudges,
+            vec![(Vec2i::new(4, 0), Vec2i::new(4, 1))]
+        );
+
+        let pattern = input_generator("#.\n..").remove(0);
+        assert_eq!(
+            find_reflection(&pattern, 0),
+            Err(NoReflection { smudges: 0 })
+        );
+        assert_eq!(find_reflection(&pattern, 1), Ok(1));
+    }
+
+    #[test]
+    fn test_large_pattern() {
+        // 80 rows of 70 columns mirrored at row 40, every third row is a palindrome
+        let half: String = (0..35)
+            .map(|x| if (x * x) % 7 < 3 { '#' } else { '.' })
+            .collect();
+        let row = format!("{half}{}", half.chars().rev().collect::<String>());
+        let shifted = format!("{}{}", &row[1..], &row[..1]);
+        let lines: Vec<_> = (0..40)
+            .map(|y| if y % 3 == 0 { &row } else { &shifted })
+            .collect();
+        let pattern = lines
+            .iter()
+            .chain(lines.iter().rev())
+            .map(|l| l.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut grid = input_generator(&pattern).remove(0);
+        assert_eq!((grid.size_x, grid.size_y), (70, 80));
+
+        let vertical_mismatches = |reflection: &Reflection| {
+            reflection
+                .mirrors()
+                .iter()
+                .find(|m| m.axis == Axis::Vertical(35))
+                .unwrap()
+                .mismatches()
+        };
+        let reflection = Reflection::new(&grid);
+        assert!(reflection
+            .mirrors()
+            .iter()
+            .any(|m| m.axis == Axis::Horizontal(40) && m.mismatches() == 0));
+        let before = vertical_mismatches(&reflection);
+
+        let smudge = Vec2i::new(68, 79);
+        grid[smudge] = match grid[smudge] {
+            Ground::Dot => Ground::Hash,
+            Ground::Hash => Ground::Dot,
+        };
+        let reflection = Reflection::new(&grid);
+        let mirror = reflection
+            .mirrors()
+            .iter()
+            .find(|m| m.axis == Axis::Horizontal(40))
+            .unwrap();
+        assert_eq!(mirror.smudges, vec![(Vec2i::new(68, 0), smudge)]);
+        // the last row is a palindrome, so the flipped cell adds one mismatch to the vertical axis
+        assert_eq!(vertical_mismatches(&reflection), before + 1);
     }
 }
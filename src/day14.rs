@@ -1,9 +1,11 @@
-use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
 use aoc_runner_derive::{aoc, aoc_generator};
+use itertools::Itertools;
 use rustc_hash::FxHashMap;
 
-use crate::common::{Direction, Grid, Vec2i};
+use crate::common::{Direction, Grid, Rgb, Vec2i};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum Tile {
@@ -27,11 +29,19 @@ impl TryFrom<char> for Tile {
     }
 }
 
+impl From<Tile> for char {
+    fn from(value: Tile) -> Self {
+        match value {
+            Tile::Empty => '.',
+            Tile::Obstacle => '#',
+            Tile::Rock => 'O',
+        }
+    }
+}
+
 trait Platform {
     fn tilt(&self, dir: Direction) -> Self;
 
-    fn cycle(&self, n: usize) -> Self;
-
     fn total_load(&self) -> usize;
 }
 
@@ -91,46 +101,347 @@ impl Platform for Grid<Tile> {
         grid
     }
 
-    fn cycle(&self, n: usize) -> Grid<Tile> {
-        let mut current = self.clone();
+    fn total_load(&self) -> usize {
+        self.pos_iter()
+            .map(|(pos, t)| {
+                if *t == Tile::Rock {
+                    self.size_y - pos.y as usize
+                } else {
+                    0
+                }
+            })
+            .sum()
+    }
+}
+
+/// A sequence of tilts like `NWSE`, which is the spin cycle of the puzzle.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TiltProgram(Vec<Direction>);
+
+impl TiltProgram {
+    pub fn spin() -> Self {
+        TiltProgram(vec![
+            Direction::North,
+            Direction::West,
+            Direction::South,
+            Direction::East,
+        ])
+    }
+
+    pub fn directions(&self) -> &[Direction] {
+        &self.0
+    }
+}
+
+impl FromStr for TiltProgram {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let directions = s
+            .trim()
+            .chars()
+            .map(|c| match c {
+                'N' => Ok(Direction::North),
+                'E' => Ok(Direction::East),
+                'S' => Ok(Direction::South),
+                'W' => Ok(Direction::West),
+                _ => Err(()),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if directions.is_empty() {
+            return Err(());
+        }
+
+        Ok(TiltProgram(directions))
+    }
+}
+
+impl Display for TiltProgram {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for dir in &self.0 {
+            write!(
+                f,
+                "{}",
+                match dir {
+                    Direction::North => 'N',
+                    Direction::East => 'E',
+                    Direction::South => 'S',
+                    Direction::West => 'W',
+                }
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// After `prefix` runs of a program the states repeat every `period` runs.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Period {
+    pub prefix: usize,
+    pub period: usize,
+}
+
+/// A platform where the free tiles are split into runs between obstacles for every direction.
+/// Tilting counts the rocks per run and refills every run from its wall, which takes time
+/// proportional to the number of rocks and runs instead of touching every tile.
+#[derive(Debug, Clone)]
+pub struct SegmentPlatform {
+    size_x: usize,
+    size_y: usize,
+    obstacles: Vec<bool>,
+    /// runs for every direction, in the order of [`Direction::VALUES`]
+    runs: [Runs; 4],
+    /// random key for every tile, the state hash is the xor of the keys of all rocks
+    keys: Vec<u128>,
+    rocks: Vec<u32>,
+    hash: u128,
+    /// rocks per run while tilting, kept to avoid allocating on every tilt
+    counts: Vec<u32>,
+}
+
+/// The runs of free tiles for tilting in one direction.
+#[derive(Debug, Clone)]
+struct Runs {
+    /// the run of each tile
+    run_of: Vec<u32>,
+    /// the tiles of each run, starting at the wall the rocks roll against
+    tiles: Vec<Vec<u32>>,
+}
+
+impl SegmentPlatform {
+    pub fn new(grid: &Grid<Tile>) -> Self {
+        let index = |pos: Vec2i| pos.x as usize + grid.size_x * pos.y as usize;
+        // tiles are numbered in the same row-major order as they are stored in the grid
+        let obstacles = grid
+            .pos_iter()
+            .map(|(_, t)| *t == Tile::Obstacle)
+            .collect_vec();
+
+        let runs = Direction::VALUES.map(|dir| {
+            let mut runs = Runs {
+                run_of: vec![u32::MAX; obstacles.len()],
+                tiles: vec![],
+            };
+            // walk every line away from the wall the rocks roll against
+            let back = dir.opposite();
+            let walls = grid
+                .pos_iter()
+                .map(|(pos, _)| pos)
+                .filter(|pos| !grid.in_bounds(&dir.offset(pos)));
+            for mut pos in walls {
+                let mut run = vec![];
+                while grid.in_bounds(&pos) {
+                    let i = index(pos);
+                    if obstacles[i] {
+                        if !run.is_empty() {
+                            runs.tiles.push(std::mem::take(&mut run));
+                        }
+                    } else {
+                        runs.run_of[i] = runs.tiles.len() as u32;
+                        run.push(i as u32);
+                    }
+                    pos = back.offset(&pos);
+                }
+                if !run.is_empty() {
+                    runs.tiles.push(run);
+                }
+            }
+            runs
+        });
+
+        // splitmix64
+        let mut seed = 0x9e3779b97f4a7c15u64;
+        let mut next = || {
+            seed = seed.wrapping_add(0x9e3779b97f4a7c15);
+            let mut z = seed;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+            z ^ (z >> 31)
+        };
+        let keys = (0..obstacles.len())
+            .map(|_| ((next() as u128) << 64) | next() as u128)
+            .collect_vec();
+
+        let rocks = grid
+            .pos_iter()
+            .filter(|(_, t)| **t == Tile::Rock)
+            .map(|(pos, _)| index(pos) as u32)
+            .collect_vec();
+        let hash = rocks.iter().fold(0, |h, &r| h ^ keys[r as usize]);
+
+        SegmentPlatform {
+            size_x: grid.size_x,
+            size_y: grid.size_y,
+            obstacles,
+            runs,
+            keys,
+            rocks,
+            hash,
+            counts: vec![],
+        }
+    }
+
+    pub fn tilt(&mut self, dir: Direction) {
+        let runs = &self.runs[dir.index()];
+        self.counts.clear();
+        self.counts.resize(runs.tiles.len(), 0);
+        for &rock in &self.rocks {
+            self.counts[runs.run_of[rock as usize] as usize] += 1;
+        }
+
+        self.rocks.clear();
+        self.hash = 0;
+        for (run, &count) in runs.tiles.iter().zip(&self.counts) {
+            for &tile in &run[..count as usize] {
+                self.rocks.push(tile);
+                self.hash ^= self.keys[tile as usize];
+            }
+        }
+    }
+
+    pub fn run(&mut self, program: &TiltProgram) {
+        for &dir in program.directions() {
+            self.tilt(dir);
+        }
+    }
 
-        let mut cache: FxHashMap<Grid<Tile>, usize> = HashMap::default();
+    /// Runs the program `n` times, skipping ahead once a state repeats. Only the hashes of the
+    /// visited states are kept.
+    pub fn run_n(&mut self, program: &TiltProgram, n: usize) {
+        let mut seen: FxHashMap<u128, usize> = FxHashMap::default();
         let mut i = 0;
-        loop {
-            if i == n {
-                return current;
+        while i < n {
+            if let Some(prev_i) = seen.insert(self.hash, i) {
+                let remaining = (n - i) % (i - prev_i);
+                for _ in 0..remaining {
+                    self.run(program);
+                }
+                return;
             }
 
-            let next = current
-                .tilt(Direction::North)
-                .tilt(Direction::West)
-                .tilt(Direction::South)
-                .tilt(Direction::East);
+            self.run(program);
             i += 1;
+        }
+    }
 
-            if let Some(prev_i) = cache.get(&next) {
-                let cycle_length = i - prev_i;
-                let how_many_cycles = (n - i) / cycle_length;
-                i += how_many_cycles * cycle_length;
-            } else {
-                cache.insert(next.clone(), i);
+    /// Finds after how many runs of the program the states start repeating.
+    pub fn period(&self, program: &TiltProgram) -> Period {
+        let mut platform = self.clone();
+        let mut seen: FxHashMap<u128, usize> = FxHashMap::default();
+        let mut i = 0;
+        loop {
+            if let Some(prev_i) = seen.insert(platform.hash, i) {
+                return Period {
+                    prefix: prev_i,
+                    period: i - prev_i,
+                };
             }
 
-            current = next;
+            platform.run(program);
+            i += 1;
         }
     }
 
-    fn total_load(&self) -> usize {
-        self.pos_iter()
-            .map(|(pos, t)| {
-                if *t == Tile::Rock {
-                    self.size_y - pos.y as usize
-                } else {
-                    0
+    /// A 128 bit hash of the rock positions, independent of their order.
+    pub fn state_hash(&self) -> u128 {
+        self.hash
+    }
+
+    /// Sum of the loads of all rocks, where the load of a rock is the number of rows or columns
+    /// from the opposite wall up to and including its own.
+    pub fn load(&self, wall: Direction) -> usize {
+        self.rocks
+            .iter()
+            .map(|&r| {
+                let pos = self.position(r);
+                match wall {
+                    Direction::North => self.size_y - pos.y as usize,
+                    Direction::South => pos.y as usize + 1,
+                    Direction::West => self.size_x - pos.x as usize,
+                    Direction::East => pos.x as usize + 1,
                 }
             })
             .sum()
     }
+
+    pub fn total_load(&self) -> usize {
+        self.load(Direction::North)
+    }
+
+    pub fn to_grid(&self) -> Grid<Tile> {
+        let mut grid = Grid::new_from_element(self.size_x, self.size_y, Tile::Empty);
+        for (i, _) in self.obstacles.iter().enumerate().filter(|(_, o)| **o) {
+            grid[self.position(i as u32)] = Tile::Obstacle;
+        }
+        for &r in &self.rocks {
+            grid[self.position(r)] = Tile::Rock;
+        }
+        grid
+    }
+
+    fn position(&self, tile: u32) -> Vec2i {
+        let tile = tile as usize;
+        Vec2i::new((tile % self.size_x) as _, (tile / self.size_x) as _)
+    }
+}
+
+/// Intermediate states of a tilt, where each step moves every rock that is not blocked by one
+/// tile. The initial state is not included and the last state is the fully tilted platform.
+pub fn tilt_frames(input: &Grid<Tile>, dir: Direction) -> impl Iterator<Item = Grid<Tile>> {
+    std::iter::successors(Some(input.clone()), move |grid| {
+        let mut next = grid.clone();
+        let mut moved = false;
+        for (pos, t) in grid.pos_iter() {
+            let target = dir.offset(&pos);
+            if *t == Tile::Rock && grid.in_bounds(&target) && grid[target] == Tile::Empty {
+                next[pos] = Tile::Empty;
+                next[target] = Tile::Rock;
+                moved = true;
+            }
+        }
+
+        moved.then_some(next)
+    })
+    .skip(1)
+}
+
+/// Intermediate states of `runs` runs of a tilt program, see [`tilt_frames`].
+pub fn program_frames(
+    input: &Grid<Tile>,
+    program: &TiltProgram,
+    runs: usize,
+) -> impl Iterator<Item = Grid<Tile>> {
+    let mut current = input.clone();
+    let steps = program.directions().len() * runs;
+    program
+        .directions()
+        .to_vec()
+        .into_iter()
+        .cycle()
+        .take(steps)
+        .flat_map(move |dir| {
+            let frames: Vec<_> = tilt_frames(&current, dir).collect();
+            if let Some(last) = frames.last() {
+                current = last.clone();
+            }
+            frames
+        })
+}
+
+/// Intermediate states of `cycles` spin cycles, see [`tilt_frames`].
+pub fn spin_frames(input: &Grid<Tile>, cycles: usize) -> impl Iterator<Item = Grid<Tile>> {
+    program_frames(input, &TiltProgram::spin(), cycles)
+}
+
+/// Renders the platform after `runs` runs of a tilt program.
+pub fn render_platform(input: &Grid<Tile>, program: &TiltProgram, runs: usize) -> Grid<Rgb> {
+    let mut platform = SegmentPlatform::new(input);
+    platform.run_n(program, runs);
+    platform.to_grid().map(|_, t| match t {
+        Tile::Empty => Rgb::WHITE,
+        Tile::Obstacle => Rgb(0x55, 0x55, 0x55),
+        Tile::Rock => Rgb(0xaa, 0x66, 0x22),
+    })
 }
 
 #[aoc_generator(day14)]
@@ -145,7 +456,9 @@ pub fn part1(input: &Grid<Tile>) -> usize {
 
 #[aoc(day14, part2)]
 pub fn part2(input: &Grid<Tile>) -> usize {
-    input.cycle(1_000_000_000).total_load()
+    let mut platform = SegmentPlatform::new(input);
+    platform.run_n(&TiltProgram::spin(), 1_000_000_000);
+    platform.total_load()
 }
 
 #[cfg(test)]
@@ -153,6 +466,7 @@ mod tests {
     use pretty_assertions::assert_eq;
 
     use super::*;
+    use crate::common::XorShift;
 
     const INPUT: &str = r#"O....#....
 O.OO#....#
@@ -174,4 +488,131 @@ O.#..O.#.#
     fn test_part2() {
         assert_eq!(part2(&input_generator(INPUT)), 64);
     }
+
+    #[test]
+    fn test_render_platform() {
+        const AFTER_1_CYCLE: &str = r#".....#....
+....#...O#
+...OO##...
+.OO#......
+.....OOO#.
+.O#...O#.#
+....O#....
+......OOOO
+#...O###..
+#..OO#...."#;
+
+        let expected = input_generator(AFTER_1_CYCLE);
+        let image = render_platform(&input_generator(INPUT), &TiltProgram::spin(), 1);
+        assert!(expected
+            .pos_iter()
+            .all(|(pos, t)| (*t == Tile::Rock) == (image[pos] == Rgb(0xaa, 0x66, 0x22))));
+    }
+
+    #[test]
+    fn test_frames() {
+        let input = input_generator(INPUT);
+        let frames: Vec<_> = tilt_frames(&input, Direction::North).collect();
+        assert_eq!(frames.len(), 7);
+        assert_eq!(frames.last(), Some(&input.tilt(Direction::North)));
+
+        let frames: Vec<_> = spin_frames(&input, 3).collect();
+        let mut platform = SegmentPlatform::new(&input);
+        platform.run_n(&TiltProgram::spin(), 3);
+        assert_eq!(frames.last(), Some(&platform.to_grid()));
+        assert_eq!(frames.last().unwrap().total_load(), 69);
+    }
+
+    #[test]
+    fn test_segment_platform() {
+        let grid = XorShift::default().grid(37, 23, 8, |n| match n {
+            0 => Tile::Obstacle,
+            1..=3 => Tile::Rock,
+            _ => Tile::Empty,
+        });
+
+        let mut platform = SegmentPlatform::new(&grid);
+        assert_eq!(platform.to_grid(), grid);
+        let mut expected = grid.clone();
+        for dir in [
+            Direction::North,
+            Direction::East,
+            Direction::South,
+            Direction::West,
+            Direction::North,
+        ] {
+            platform.tilt(dir);
+            expected = expected.tilt(dir);
+            assert_eq!(platform.to_grid(), expected);
+            assert_eq!(
+                platform.state_hash(),
+                SegmentPlatform::new(&expected).state_hash()
+            );
+        }
+        assert_eq!(platform.total_load(), expected.total_load());
+
+        let mut platform = SegmentPlatform::new(&input_generator(INPUT));
+        platform.run_n(&TiltProgram::spin(), 3);
+        assert_eq!(
+            Some(platform.to_grid()),
+            spin_frames(&input_generator(INPUT), 3).last()
+        );
+    }
+
+    fn rocks_count(grid: &Grid<Tile>) -> usize {
+        grid.pos_iter().filter(|(_, t)| **t == Tile::Rock).count()
+    }
+
+    #[test]
+    fn test_programs() {
+        assert_eq!("NWSE".parse(), Ok(TiltProgram::spin()));
+        assert_eq!("NNEW".parse::<TiltProgram>().unwrap().to_string(), "NNEW");
+        assert_eq!("".parse::<TiltProgram>(), Err(()));
+        assert_eq!("NX".parse::<TiltProgram>(), Err(()));
+        assert_eq!("URDL".parse::<TiltProgram>(), Err(()));
+
+        let input = input_generator(INPUT);
+        let platform = SegmentPlatform::new(&input);
+        let spin = TiltProgram::spin();
+        assert_eq!(
+            platform.period(&spin),
+            Period {
+                prefix: 3,
+                period: 7
+            }
+        );
+        assert_eq!(
+            platform.period(&"N".parse().unwrap()),
+            Period {
+                prefix: 1,
+                period: 1
+            }
+        );
+
+        let mut tilted = platform.clone();
+        tilted.run(&"N".parse().unwrap());
+        assert_eq!(tilted.load(Direction::North), 136);
+        assert_eq!(
+            tilted.load(Direction::North) + tilted.load(Direction::South),
+            rocks_count(&input) * (input.size_y + 1)
+        );
+        let mut east = platform.clone();
+        east.run(&"NNE".parse().unwrap());
+        let grid = east.to_grid();
+        let rocks = grid.pos_iter().filter(|(_, t)| **t == Tile::Rock);
+        assert_eq!(
+            east.load(Direction::East),
+            rocks.map(|(pos, _)| pos.x as usize + 1).sum()
+        );
+        assert_eq!(
+            east.load(Direction::East) + east.load(Direction::West),
+            rocks_count(&grid) * (input.size_x + 1)
+        );
+
+        let program = "NNEW".parse().unwrap();
+        let frames: Vec<_> = program_frames(&input, &program, 2).collect();
+        let mut expected = platform.clone();
+        expected.run_n(&program, 2);
+        assert_eq!(frames.last(), Some(&expected.to_grid()));
+    }
 }
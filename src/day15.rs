@@ -1,7 +1,7 @@
-use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
 use aoc_runner_derive::{aoc, aoc_generator};
-use rustc_hash::FxHashMap;
 
 use crate::common::parse_split;
 
@@ -10,7 +10,7 @@ pub fn input_generator(input: &str) -> Vec<String> {
     parse_split(input, ',').unwrap()
 }
 
-fn hash(s: &str) -> u8 {
+pub fn hash(s: &str) -> u8 {
     debug_assert!(s.is_ascii());
     let mut n = 0u8;
     s.bytes()
@@ -18,49 +18,219 @@ fn hash(s: &str) -> u8 {
     n
 }
 
-#[aoc(day15, part1)]
-pub fn part1(input: &[String]) -> usize {
-    input.iter().map(|s| hash(s) as usize).sum()
+/// One operation of the initialization sequence.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Step {
+    Insert { label: String, focal_length: u8 },
+    Remove { label: String },
 }
 
-#[aoc(day15, part2)]
-pub fn part2(input: &[String]) -> usize {
-    let mut boxes: FxHashMap<u8, Vec<(&str, u8)>> = HashMap::default();
-    for s in input {
+impl FromStr for Step {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         if let Some((label, focal_length)) = s.split_once('=') {
-            let focal_length = focal_length.parse().unwrap();
-            let box_contents = boxes.entry(hash(label)).or_default();
-            if let Some((_, ex_focal_length)) = box_contents
-                .iter_mut()
-                .find(|(ex_label, _)| *ex_label == label)
-            {
-                *ex_focal_length = focal_length;
-            } else {
-                box_contents.push((label, focal_length));
-            }
+            Ok(Step::Insert {
+                label: label.to_owned(),
+                focal_length: focal_length.parse().map_err(|_| ())?,
+            })
         } else if let Some(label) = s.strip_suffix('-') {
-            let box_contents = boxes.entry(hash(label)).or_default();
-            if let Some(pos) = box_contents
-                .iter()
-                .position(|(ex_label, _)| *ex_label == label)
-            {
-                box_contents.remove(pos);
-            }
+            Ok(Step::Remove {
+                label: label.to_owned(),
+            })
         } else {
-            panic!("invalid input");
+            Err(())
+        }
+    }
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Step::Insert {
+                label,
+                focal_length,
+            } => write!(f, "{label}={focal_length}"),
+            Step::Remove { label } => write!(f, "{label}-"),
+        }
+    }
+}
+
+/// What an operation did to the box of its label.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Change {
+    Inserted {
+        bucket: usize,
+        slot: usize,
+    },
+    Replaced {
+        bucket: usize,
+        slot: usize,
+        previous: u8,
+    },
+    Removed {
+        bucket: usize,
+        slot: usize,
+        focal_length: u8,
+    },
+    Missing {
+        bucket: usize,
+    },
+}
+
+/// The boxes of the HASHMAP procedure: every label goes into the box chosen by the hash function
+/// and keeps its slot until it is removed, even when its focal length changes.
+#[derive(Debug, Clone)]
+pub struct LensBoxes<F = fn(&str) -> usize> {
+    boxes: Vec<Vec<(String, u8)>>,
+    hash: F,
+}
+
+impl LensBoxes {
+    /// The 256 boxes of the puzzle, using [`hash`].
+    pub fn new() -> Self {
+        LensBoxes::with_hasher(256, |s| hash(s) as usize)
+    }
+}
+
+impl Default for LensBoxes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: Fn(&str) -> usize> LensBoxes<F> {
+    /// Boxes for any hash function, whose results are taken modulo the number of boxes.
+    pub fn with_hasher(buckets: usize, hash: F) -> Self {
+        assert!(buckets > 0, "assume: at least one box");
+        LensBoxes {
+            boxes: vec![vec![]; buckets],
+            hash,
+        }
+    }
+
+    pub fn buckets(&self) -> usize {
+        self.boxes.len()
+    }
+
+    pub fn bucket(&self, label: &str) -> usize {
+        (self.hash)(label) % self.boxes.len()
+    }
+
+    fn slot(&self, label: &str) -> (usize, Option<usize>) {
+        let bucket = self.bucket(label);
+        let slot = self.boxes[bucket].iter().position(|(l, _)| l == label);
+        (bucket, slot)
+    }
+
+    /// Replaces the focal length of the lens with this label or adds it to the back of its box.
+    pub fn insert(&mut self, label: &str, focal_length: u8) -> Change {
+        match self.slot(label) {
+            (bucket, Some(slot)) => {
+                let previous = std::mem::replace(&mut self.boxes[bucket][slot].1, focal_length);
+                Change::Replaced {
+                    bucket,
+                    slot,
+                    previous,
+                }
+            }
+            (bucket, None) => {
+                self.boxes[bucket].push((label.to_owned(), focal_length));
+                Change::Inserted {
+                    bucket,
+                    slot: self.boxes[bucket].len() - 1,
+                }
+            }
+        }
+    }
+
+    /// Takes the lens with this label out of its box, moving the lenses behind it forward.
+    pub fn remove(&mut self, label: &str) -> Change {
+        match self.slot(label) {
+            (bucket, Some(slot)) => {
+                let (_, focal_length) = self.boxes[bucket].remove(slot);
+                Change::Removed {
+                    bucket,
+                    slot,
+                    focal_length,
+                }
+            }
+            (bucket, None) => Change::Missing { bucket },
         }
     }
 
-    boxes
-        .iter()
-        .flat_map(|(n, box_contents)| {
-            let box_id = *n as usize + 1;
-            box_contents
+    pub fn get(&self, label: &str) -> Option<u8> {
+        let (bucket, slot) = self.slot(label);
+        slot.map(|slot| self.boxes[bucket][slot].1)
+    }
+
+    pub fn apply(&mut self, step: &Step) -> Change {
+        match step {
+            Step::Insert {
+                label,
+                focal_length,
+            } => self.insert(label, *focal_length),
+            Step::Remove { label } => self.remove(label),
+        }
+    }
+
+    /// Applies all steps, recording what each of them changed.
+    pub fn trace<'a>(&mut self, steps: impl IntoIterator<Item = &'a Step>) -> Vec<Change> {
+        steps.into_iter().map(|step| self.apply(step)).collect()
+    }
+
+    /// The lenses of one box, front to back.
+    pub fn lenses(&self, bucket: usize) -> impl Iterator<Item = (&str, u8)> {
+        self.boxes[bucket].iter().map(|(l, f)| (l.as_str(), *f))
+    }
+
+    /// All lenses as `(bucket, slot, label, focal length)`, ordered by box and slot.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, &str, u8)> {
+        self.boxes.iter().enumerate().flat_map(|(bucket, lenses)| {
+            lenses
                 .iter()
                 .enumerate()
-                .map(move |(i, (_, focal_length))| box_id * (i + 1) * *focal_length as usize)
+                .map(move |(slot, (l, f))| (bucket, slot, l.as_str(), *f))
         })
-        .sum()
+    }
+
+    pub fn focusing_power(&self) -> usize {
+        self.iter()
+            .map(|(bucket, slot, _, f)| (bucket + 1) * (slot + 1) * f as usize)
+            .sum()
+    }
+}
+
+/// The non-empty boxes in the format of the puzzle description.
+impl<F> Display for LensBoxes<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (bucket, lenses) in self.boxes.iter().enumerate() {
+            if lenses.is_empty() {
+                continue;
+            }
+
+            write!(f, "Box {bucket}:")?;
+            for (label, focal_length) in lenses {
+                write!(f, " [{label} {focal_length}]")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[aoc(day15, part1)]
+pub fn part1(input: &[String]) -> usize {
+    input.iter().map(|s| hash(s) as usize).sum()
+}
+
+#[aoc(day15, part2)]
+pub fn part2(input: &[String]) -> usize {
+    let mut boxes = LensBoxes::new();
+    for s in input {
+        boxes.apply(&s.parse().expect("invalid input"));
+    }
+    boxes.focusing_power()
 }
 
 #[cfg(test)]
@@ -85,4 +255,74 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(&input_generator(INPUT)), 145);
     }
+
+    #[test]
+    fn test_lens_boxes() {
+        let steps: Vec<Step> = input_generator(INPUT)
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        assert_eq!(steps[1].to_string(), "cm-");
+
+        let mut boxes = LensBoxes::new();
+        let trace = boxes.trace(&steps[..4]);
+        assert_eq!(
+            trace,
+            vec![
+                Change::Inserted { bucket: 0, slot: 0 },
+                Change::Missing { bucket: 0 },
+                Change::Inserted { bucket: 1, slot: 0 },
+                Change::Inserted { bucket: 0, slot: 1 },
+            ]
+        );
+        assert_eq!(boxes.to_string(), "Box 0: [rn 1] [cm 2]\nBox 1: [qp 3]\n");
+        assert_eq!(
+            boxes.trace(&steps[4..]).last(),
+            Some(&Change::Replaced {
+                bucket: 3,
+                slot: 0,
+                previous: 9
+            })
+        );
+        assert_eq!(
+            boxes.to_string(),
+            "Box 0: [rn 1] [cm 2]\nBox 3: [ot 7] [ab 5] [pc 6]\n"
+        );
+        assert_eq!(boxes.get("ab"), Some(5));
+        assert_eq!(boxes.get("qp"), None);
+        assert_eq!(boxes.focusing_power(), 145);
+
+        assert_eq!(
+            boxes.remove("ab"),
+            Change::Removed {
+                bucket: 3,
+                slot: 1,
+                focal_length: 5
+            }
+        );
+        assert_eq!(
+            boxes.lenses(3).collect::<Vec<_>>(),
+            vec![("ot", 7), ("pc", 6)]
+        );
+    }
+
+    #[test]
+    fn test_custom_hasher() {
+        let mut boxes = LensBoxes::with_hasher(3, |s: &str| s.len());
+        boxes.insert("a", 1);
+        boxes.insert("bb", 2);
+        boxes.insert("ccc", 3);
+        boxes.insert("dddd", 4);
+        assert_eq!(boxes.buckets(), 3);
+        assert_eq!(
+            boxes.iter().collect::<Vec<_>>(),
+            vec![
+                (0, 0, "ccc", 3),
+                (1, 0, "a", 1),
+                (1, 1, "dddd", 4),
+                (2, 0, "bb", 2)
+            ]
+        );
+        assert_eq!(boxes.focusing_power(), 3 + 2 + 2 * 2 * 4 + 3 * 2);
+    }
 }
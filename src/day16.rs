@@ -1,7 +1,8 @@
 use aoc_runner_derive::{aoc, aoc_generator};
-use rustc_hash::FxHashSet;
+use pathfinding::prelude::strongly_connected_components;
+use rustc_hash::{FxHashMap, FxHashSet};
 
-use crate::common::{Direction, Grid, Vec2i};
+use crate::common::{Direction, Grid, Rgb, Vec2i};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum Tile {
@@ -29,57 +30,549 @@ impl TryFrom<char> for Tile {
     }
 }
 
-fn simulate(grid: &Grid<Tile>, initial: &(Vec2i, Direction)) -> FxHashSet<Vec2i> {
-    let mut visited: FxHashSet<(Vec2i, Direction)> = FxHashSet::default();
-    let mut q = vec![*initial];
-    while let Some((pos, dir)) = q.pop() {
-        if !grid.in_bounds(&pos) || !visited.insert((pos, dir)) {
-            continue;
+impl From<Tile> for char {
+    fn from(value: Tile) -> Self {
+        match value {
+            Tile::Empty => '.',
+            Tile::ForwardMirror => '/',
+            Tile::BackwardMirror => '\\',
+            Tile::VerticalSplitter => '|',
+            Tile::HorizontalSplitter => '-',
         }
+    }
+}
 
-        match grid[pos] {
-            Tile::Empty => q.push((dir.offset(&pos), dir)),
-            Tile::ForwardMirror => {
-                let out_dir = match dir {
-                    Direction::North => Direction::East,
-                    Direction::South => Direction::West,
-                    Direction::East => Direction::North,
-                    Direction::West => Direction::South,
-                };
-                q.push((out_dir.offset(&pos), out_dir));
-            }
-            Tile::BackwardMirror => {
-                let out_dir = match dir {
-                    Direction::North => Direction::West,
-                    Direction::South => Direction::East,
-                    Direction::East => Direction::South,
-                    Direction::West => Direction::North,
-                };
-                q.push((out_dir.offset(&pos), out_dir));
-            }
+/// Directions of the beams leaving an optical element, at most one beam per direction.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Beams(u8);
+
+impl Beams {
+    pub const NONE: Beams = Beams(0);
+
+    fn bit(dir: Direction) -> u8 {
+        1 << dir.index()
+    }
+
+    pub fn one(dir: Direction) -> Self {
+        Beams(Self::bit(dir))
+    }
+
+    pub fn with(self, dir: Direction) -> Self {
+        Beams(self.0 | Self::bit(dir))
+    }
+
+    pub fn contains(&self, dir: Direction) -> bool {
+        self.0 & Self::bit(dir) != 0
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = Direction> {
+        Direction::VALUES
+            .into_iter()
+            .filter(move |&dir| self.contains(dir))
+    }
+}
+
+impl FromIterator<Direction> for Beams {
+    fn from_iter<I: IntoIterator<Item = Direction>>(iter: I) -> Self {
+        iter.into_iter().fold(Beams::NONE, Beams::with)
+    }
+}
+
+/// An optical element, which decides where beams go after entering its tile.
+pub trait Optics {
+    /// Directions of the beams leaving the element when a beam enters it going in `dir`.
+    fn outgoing(&self, dir: Direction) -> Beams;
+
+    /// Whether every beam passes the element straight through, just like an empty tile.
+    fn is_transparent(&self) -> bool {
+        Direction::VALUES
+            .iter()
+            .all(|&dir| self.outgoing(dir) == Beams::one(dir))
+    }
+
+    /// Character the element is drawn as.
+    fn symbol(&self) -> char;
+}
+
+impl Optics for Tile {
+    fn outgoing(&self, dir: Direction) -> Beams {
+        match self {
+            Tile::Empty => Beams::one(dir),
+            Tile::ForwardMirror => Beams::one(Mirror::Forward.reflect(dir)),
+            Tile::BackwardMirror => Beams::one(Mirror::Backward.reflect(dir)),
             Tile::VerticalSplitter => match dir {
-                Direction::North | Direction::South => {
-                    q.push((dir.offset(&pos), dir));
-                }
+                Direction::North | Direction::South => Beams::one(dir),
                 Direction::East | Direction::West => {
-                    q.push((Direction::North.offset(&pos), Direction::North));
-                    q.push((Direction::South.offset(&pos), Direction::South));
+                    Beams::one(Direction::North).with(Direction::South)
                 }
             },
             Tile::HorizontalSplitter => match dir {
                 Direction::North | Direction::South => {
-                    q.push((Direction::East.offset(&pos), Direction::East));
-                    q.push((Direction::West.offset(&pos), Direction::West));
-                }
-                Direction::East | Direction::West => {
-                    q.push((dir.offset(&pos), dir));
+                    Beams::one(Direction::East).with(Direction::West)
                 }
+                Direction::East | Direction::West => Beams::one(dir),
             },
         }
     }
 
+    fn is_transparent(&self) -> bool {
+        *self == Tile::Empty
+    }
+
+    fn symbol(&self) -> char {
+        char::from(*self)
+    }
+}
+
+impl Optics for Box<dyn Optics> {
+    fn outgoing(&self, dir: Direction) -> Beams {
+        self.as_ref().outgoing(dir)
+    }
+
+    fn is_transparent(&self) -> bool {
+        self.as_ref().is_transparent()
+    }
+
+    fn symbol(&self) -> char {
+        self.as_ref().symbol()
+    }
+}
+
+/// Swallows every beam.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Absorber;
+
+impl Optics for Absorber {
+    fn outgoing(&self, _dir: Direction) -> Beams {
+        Beams::NONE
+    }
+
+    fn symbol(&self) -> char {
+        '@'
+    }
+}
+
+/// Orientation of a mirror.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Mirror {
+    /// `/`
+    Forward,
+    /// `\`
+    Backward,
+}
+
+impl Mirror {
+    /// Direction of a beam going in `dir` after it is reflected by the mirror.
+    pub fn reflect(&self, dir: Direction) -> Direction {
+        match (self, dir) {
+            (Mirror::Forward, Direction::North) | (Mirror::Backward, Direction::South) => {
+                Direction::East
+            }
+            (Mirror::Forward, Direction::South) | (Mirror::Backward, Direction::North) => {
+                Direction::West
+            }
+            (Mirror::Forward, Direction::East) | (Mirror::Backward, Direction::West) => {
+                Direction::North
+            }
+            (Mirror::Forward, Direction::West) | (Mirror::Backward, Direction::East) => {
+                Direction::South
+            }
+        }
+    }
+}
+
+/// A mirror that is only reflective on one side: beams going in one of the `reflected`
+/// directions are turned by `mirror`, all others pass straight through.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct OneWayMirror {
+    pub mirror: Mirror,
+    pub reflected: [Direction; 2],
+}
+
+impl Optics for OneWayMirror {
+    fn outgoing(&self, dir: Direction) -> Beams {
+        if self.reflected.contains(&dir) {
+            Beams::one(self.mirror.reflect(dir))
+        } else {
+            Beams::one(dir)
+        }
+    }
+
+    fn symbol(&self) -> char {
+        match self.mirror {
+            Mirror::Forward => '/',
+            Mirror::Backward => '\\',
+        }
+    }
+}
+
+/// Splits every beam three ways: straight on and to both sides.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Prism;
+
+impl Optics for Prism {
+    fn outgoing(&self, dir: Direction) -> Beams {
+        [dir, dir.rotate_ccw(), dir.rotate_cw()]
+            .into_iter()
+            .collect()
+    }
+
+    fn symbol(&self) -> char {
+        '+'
+    }
+}
+
+/// All beam states reached from the given sources.
+fn propagate<T: Optics>(
+    grid: &Grid<T>,
+    sources: &[(Vec2i, Direction)],
+) -> FxHashSet<(Vec2i, Direction)> {
+    let mut visited: FxHashSet<(Vec2i, Direction)> = FxHashSet::default();
+    let mut q = sources.to_vec();
+    while let Some((pos, dir)) = q.pop() {
+        if !grid.in_bounds(&pos) || !visited.insert((pos, dir)) {
+            continue;
+        }
+
+        q.extend(
+            grid[pos]
+                .outgoing(dir)
+                .iter()
+                .map(|out_dir| (out_dir.offset(&pos), out_dir)),
+        );
+    }
+
+    visited
+}
+
+fn simulate<T: Optics>(grid: &Grid<T>, initial: &(Vec2i, Direction)) -> FxHashSet<Vec2i> {
     // unique().count() from itertools did not work
-    visited.iter().map(|(pos, _)| *pos).collect()
+    propagate(grid, &[*initial])
+        .iter()
+        .map(|(pos, _)| *pos)
+        .collect()
+}
+
+/// Follows the beams of all sources at once and counts for every tile how many distinct beams,
+/// told apart by their direction, entered it.
+pub fn beam_hits<T: Optics>(grid: &Grid<T>, sources: &[(Vec2i, Direction)]) -> Grid<usize> {
+    let mut hits = grid.map(|_, _| 0);
+    for (pos, _) in propagate(grid, sources) {
+        hits[pos] += 1;
+    }
+    hits
+}
+
+/// A set of tiles as a bitset over their row-major indices.
+#[derive(Debug, Clone)]
+struct TileSet(Vec<u64>);
+
+impl TileSet {
+    fn new(tiles: usize) -> Self {
+        TileSet(vec![0; tiles.div_ceil(64)])
+    }
+
+    fn insert(&mut self, tile: usize) {
+        self.0[tile / 64] |= 1 << (tile % 64);
+    }
+
+    fn union(&mut self, other: &TileSet) {
+        self.0.iter_mut().zip(&other.0).for_each(|(a, b)| *a |= b);
+    }
+
+    fn len(&self) -> usize {
+        self.0.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().enumerate().flat_map(|(i, &w)| {
+            (0..64)
+                .filter(move |bit| w & (1 << bit) != 0)
+                .map(move |bit| 64 * i + bit)
+        })
+    }
+}
+
+/// The beams between optical elements. A node is a beam entering an element from some
+/// direction and its edges are the straight segments of empty tiles to the next elements. The
+/// strongly connected components of this graph energize the same tiles. Components reached from
+/// more than one other component keep their energized set, which is shared by everything leading
+/// there, while the tiles of all other components are collected again whenever they are needed.
+/// That only stores as many full-grid sets as there are merge points in the component DAG.
+#[derive(Debug, Clone)]
+pub struct BeamGraph<'a, T> {
+    grid: &'a Grid<T>,
+    node_index: FxHashMap<(Vec2i, Direction), usize>,
+    component_of: Vec<usize>,
+    /// tiles on the segments leaving the nodes of each component
+    own: Vec<Vec<usize>>,
+    successors: Vec<Vec<usize>>,
+    /// energized tiles of the components with more than one predecessor
+    shared: Vec<Option<TileSet>>,
+}
+
+impl<'a, T: Optics> BeamGraph<'a, T> {
+    pub fn new(grid: &'a Grid<T>) -> Self {
+        let nodes: Vec<_> = grid
+            .pos_iter()
+            .filter(|(_, t)| !t.is_transparent())
+            .flat_map(|(pos, _)| Direction::VALUES.map(|dir| (pos, dir)))
+            .collect();
+        let node_index: FxHashMap<_, _> = nodes.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+
+        // segments leaving every node: the tiles they cross and the node they end in
+        let segments: Vec<Vec<(Vec<usize>, Option<usize>)>> = nodes
+            .iter()
+            .map(|&(pos, dir)| {
+                grid[pos]
+                    .outgoing(dir)
+                    .iter()
+                    .map(|out_dir| {
+                        let (tiles, end) = Self::walk(grid, out_dir.offset(&pos), out_dir);
+                        (tiles, end.map(|end| node_index[&end]))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let components =
+            strongly_connected_components(&(0..nodes.len()).collect::<Vec<_>>(), |&n| {
+                segments[n]
+                    .iter()
+                    .filter_map(|(_, end)| *end)
+                    .collect::<Vec<_>>()
+            });
+        let mut component_of = vec![0; nodes.len()];
+        for (c, component) in components.iter().enumerate() {
+            for &n in component {
+                component_of[n] = c;
+            }
+        }
+
+        let mut own = vec![vec![]; components.len()];
+        let mut successors = vec![vec![]; components.len()];
+        for (n, &(pos, _)) in nodes.iter().enumerate() {
+            let c = component_of[n];
+            own[c].push(Self::index(grid, pos));
+            for (tiles, end) in &segments[n] {
+                own[c].extend(tiles);
+                if let Some(end) = end {
+                    if component_of[*end] != c {
+                        successors[c].push(component_of[*end]);
+                    }
+                }
+            }
+        }
+        let mut predecessors = vec![0; components.len()];
+        for successors in &mut successors {
+            successors.sort_unstable();
+            successors.dedup();
+            for &s in successors.iter() {
+                predecessors[s] += 1;
+            }
+        }
+
+        // topological order of the component DAG
+        let mut remaining = predecessors.clone();
+        let mut order: Vec<_> = (0..components.len())
+            .filter(|&c| remaining[c] == 0)
+            .collect();
+        let mut i = 0;
+        while i < order.len() {
+            for &s in &successors[order[i]] {
+                remaining[s] -= 1;
+                if remaining[s] == 0 {
+                    order.push(s);
+                }
+            }
+            i += 1;
+        }
+
+        let mut graph = BeamGraph {
+            grid,
+            node_index,
+            shared: vec![None; components.len()],
+            component_of,
+            own,
+            successors,
+        };
+        // successors come first, so every shared set is complete before it gets used
+        for &c in order.iter().rev() {
+            if predecessors[c] > 1 {
+                graph.shared[c] = Some(graph.component_tiles(c));
+            }
+        }
+        graph
+    }
+
+    fn index(grid: &Grid<T>, pos: Vec2i) -> usize {
+        pos.x as usize + grid.size_x * pos.y as usize
+    }
+
+    /// Follows a beam through transparent tiles until it reaches an element or leaves the grid.
+    fn walk(
+        grid: &Grid<T>,
+        mut pos: Vec2i,
+        dir: Direction,
+    ) -> (Vec<usize>, Option<(Vec2i, Direction)>) {
+        let mut tiles = vec![];
+        while grid.in_bounds(&pos) {
+            if !grid[pos].is_transparent() {
+                return (tiles, Some((pos, dir)));
+            }
+
+            tiles.push(Self::index(grid, pos));
+            pos = dir.offset(&pos);
+        }
+        (tiles, None)
+    }
+
+    /// Energized tiles of a component: the tiles of the components only reachable through it
+    /// are collected directly, the others come from their shared sets.
+    fn component_tiles(&self, component: usize) -> TileSet {
+        let mut set = TileSet::new(self.grid.size_x * self.grid.size_y);
+        let mut stack = vec![component];
+        while let Some(c) = stack.pop() {
+            for &tile in &self.own[c] {
+                set.insert(tile);
+            }
+            for &s in &self.successors[c] {
+                match &self.shared[s] {
+                    Some(shared) => set.union(shared),
+                    None => stack.push(s),
+                }
+            }
+        }
+        set
+    }
+
+    fn energized_set(&self, initial: &(Vec2i, Direction)) -> TileSet {
+        let (tiles, end) = Self::walk(self.grid, initial.0, initial.1);
+        let mut set = match end {
+            Some(end) => {
+                let component = self.component_of[self.node_index[&end]];
+                match &self.shared[component] {
+                    Some(shared) => shared.clone(),
+                    None => self.component_tiles(component),
+                }
+            }
+            None => TileSet::new(self.grid.size_x * self.grid.size_y),
+        };
+        for tile in tiles {
+            set.insert(tile);
+        }
+        set
+    }
+
+    /// Tiles energized by the given entry beam, the same as [`simulate`] finds.
+    pub fn energized(&self, initial: &(Vec2i, Direction)) -> FxHashSet<Vec2i> {
+        let size_x = self.grid.size_x;
+        self.energized_set(initial)
+            .iter()
+            .map(|tile| Vec2i::new((tile % size_x) as _, (tile / size_x) as _))
+            .collect()
+    }
+
+    pub fn energized_count(&self, initial: &(Vec2i, Direction)) -> usize {
+        self.energized_set(initial).len()
+    }
+}
+
+/// State of the beam propagation after one step.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BeamFrame {
+    /// Heads of the beams that entered a new tile in this step.
+    pub beams: Vec<(Vec2i, Direction)>,
+    /// Tiles energized so far.
+    pub energized: Grid<bool>,
+}
+
+impl BeamFrame {
+    /// Draws the beam heads as arrows and the energized transparent tiles as `#` over the
+    /// contraption.
+    pub fn render<T: Optics>(&self, grid: &Grid<T>) -> Grid<char> {
+        let mut chars = grid.map(|pos, t| {
+            if self.energized[pos] && t.is_transparent() {
+                '#'
+            } else {
+                t.symbol()
+            }
+        });
+        for (pos, dir) in &self.beams {
+            chars[*pos] = match dir {
+                Direction::North => '^',
+                Direction::South => 'v',
+                Direction::East => '>',
+                Direction::West => '<',
+            };
+        }
+        chars
+    }
+}
+
+/// Iterator over the steps of the beam propagation, see [`beam_frames`].
+#[derive(Debug, Clone)]
+pub struct BeamFrames<'a, T> {
+    grid: &'a Grid<T>,
+    visited: FxHashSet<(Vec2i, Direction)>,
+    front: Vec<(Vec2i, Direction)>,
+    energized: Grid<bool>,
+}
+
+impl<T: Optics> Iterator for BeamFrames<'_, T> {
+    type Item = BeamFrame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let beams: Vec<_> = self
+            .front
+            .drain(..)
+            .filter(|(pos, dir)| self.grid.in_bounds(pos) && self.visited.insert((*pos, *dir)))
+            .collect();
+        if beams.is_empty() {
+            return None;
+        }
+
+        for &(pos, dir) in &beams {
+            self.energized[pos] = true;
+            self.front.extend(
+                self.grid[pos]
+                    .outgoing(dir)
+                    .iter()
+                    .map(|out_dir| (out_dir.offset(&pos), out_dir)),
+            );
+        }
+
+        Some(BeamFrame {
+            beams,
+            energized: self.energized.clone(),
+        })
+    }
+}
+
+/// Propagates all beams in lockstep, one tile per step, until no beam enters an unseen state.
+pub fn beam_frames<'a, T: Optics>(
+    grid: &'a Grid<T>,
+    initial: &(Vec2i, Direction),
+) -> BeamFrames<'a, T> {
+    BeamFrames {
+        grid,
+        visited: FxHashSet::default(),
+        front: vec![*initial],
+        energized: grid.map(|_, _| false),
+    }
+}
+
+/// Renders the tiles energized by the given entry beam in yellow over the contraption.
+pub fn render_energized<T: Optics>(grid: &Grid<T>, initial: &(Vec2i, Direction)) -> Grid<Rgb> {
+    let energized = simulate(grid, initial);
+    grid.map(
+        |pos, t| match (energized.contains(&pos), t.is_transparent()) {
+            (true, true) => Rgb(0xff, 0xdd, 0x33),
+            (true, false) => Rgb(0xcc, 0x99, 0x00),
+            (false, true) => Rgb(0x22, 0x22, 0x22),
+            (false, false) => Rgb(0x77, 0x77, 0x77),
+        },
+    )
 }
 
 #[aoc_generator(day16)]
@@ -92,24 +585,35 @@ pub fn part1(input: &Grid<Tile>) -> usize {
     simulate(input, &(Vec2i::new(0, 0), Direction::East)).len()
 }
 
-#[aoc(day16, part2)]
-pub fn part2(input: &Grid<Tile>) -> usize {
-    (0..input.size_x)
+/// All beams entering the contraption from one of its edges.
+fn edge_entries<T>(grid: &Grid<T>) -> impl Iterator<Item = (Vec2i, Direction)> + '_ {
+    (0..grid.size_x)
         .flat_map(|x| {
             [
                 (Vec2i::new(x as _, 0), Direction::South),
-                (
-                    Vec2i::new(x as _, (input.size_y - 1) as _),
-                    Direction::North,
-                ),
+                (Vec2i::new(x as _, (grid.size_y - 1) as _), Direction::North),
             ]
         })
-        .chain((0..input.size_y).flat_map(|y| {
+        .chain((0..grid.size_y).flat_map(|y| {
             [
                 (Vec2i::new(0, y as _), Direction::East),
-                (Vec2i::new((input.size_x - 1) as _, y as _), Direction::West),
+                (Vec2i::new((grid.size_x - 1) as _, y as _), Direction::West),
             ]
         }))
+}
+
+#[aoc(day16, part2)]
+pub fn part2(input: &Grid<Tile>) -> usize {
+    let graph = BeamGraph::new(input);
+    edge_entries(input)
+        .map(|initial| graph.energized_count(&initial))
+        .max()
+        .unwrap()
+}
+
+#[aoc(day16, part2, simulate)]
+pub fn part2_simulate(input: &Grid<Tile>) -> usize {
+    edge_entries(input)
         .map(|initial| simulate(input, &initial).len())
         .max()
         .unwrap()
@@ -120,6 +624,7 @@ mod tests {
     use pretty_assertions::assert_eq;
 
     use super::*;
+    use crate::common::XorShift;
 
     const INPUT: &str = r#".|...\....
 |.-.\.....
@@ -140,5 +645,124 @@ mod tests {
     #[test]
     fn test_part2() {
         assert_eq!(part2(&input_generator(INPUT)), 51);
+        assert_eq!(part2_simulate(&input_generator(INPUT)), 51);
+    }
+
+    #[test]
+    fn test_render_energized() {
+        let image = render_energized(
+            &input_generator(INPUT),
+            &(Vec2i::new(3, 0), Direction::South),
+        );
+        let energized = image
+            .iter()
+            .filter(|c| matches!(c, Rgb(0xff, 0xdd, 0x33) | Rgb(0xcc, 0x99, 0x00)))
+            .count();
+        assert_eq!(energized, 51);
+    }
+
+    #[test]
+    fn test_beam_frames() {
+        let grid = input_generator(INPUT);
+        let last = beam_frames(&grid, &(Vec2i::new(0, 0), Direction::East))
+            .last()
+            .unwrap();
+        assert_eq!(last.energized.iter().filter(|e| **e).count(), 46);
+        assert!(last.render(&grid).iter().any(|c| *c == '#'));
+    }
+
+    #[test]
+    fn test_beam_graph() {
+        let random_grid = XorShift::default().grid(31, 17, 24, |n| match n {
+            0 => Tile::ForwardMirror,
+            1 => Tile::BackwardMirror,
+            2 => Tile::VerticalSplitter,
+            3 => Tile::HorizontalSplitter,
+            _ => Tile::Empty,
+        });
+
+        for grid in [input_generator(INPUT), random_grid] {
+            let graph = BeamGraph::new(&grid);
+            let inner = grid
+                .pos_iter()
+                .flat_map(|(pos, _)| Direction::VALUES.map(|dir| (pos, dir)));
+            for initial in edge_entries(&grid).chain(inner) {
+                assert_eq!(graph.energized(&initial), simulate(&grid, &initial));
+            }
+            assert_eq!(
+                graph.energized_count(&(Vec2i::new(-1, 0), Direction::East)),
+                0
+            );
+        }
+    }
+
+    #[test]
+    fn test_custom_optics() {
+        let grid: Grid<char> = r#"..#..
+.....
++...>
+....."#
+            .parse()
+            .unwrap();
+        let grid = grid.map(|_, c| -> Box<dyn Optics> {
+            match c {
+                '#' => Box::new(Absorber),
+                '+' => Box::new(Prism),
+                // turns eastbound beams north and southbound beams west, lets the others through
+                '>' => Box::new(OneWayMirror {
+                    mirror: Mirror::Forward,
+                    reflected: [Direction::East, Direction::South],
+                }),
+                c => Box::new(Tile::try_from(*c).unwrap()),
+            }
+        });
+
+        // the prism sends the beam north, east and south and the one-way mirror turns the
+        // eastbound one north
+        let hits = beam_hits(&grid, &[(Vec2i::new(0, 2), Direction::East)]);
+        let energized = hits.iter().filter(|h| **h > 0).count();
+        assert_eq!(energized, 4 + 4 + 2);
+        assert_eq!(hits[Vec2i::new(4, 0)], 1);
+
+        // the beam going south into the absorber ends there
+        let hits = beam_hits(
+            &grid,
+            &[
+                (Vec2i::new(0, 2), Direction::East),
+                (Vec2i::new(2, 0), Direction::South),
+                (Vec2i::new(3, 3), Direction::West),
+            ],
+        );
+        assert_eq!(hits[Vec2i::new(2, 0)], 1);
+        assert_eq!(hits[Vec2i::new(2, 1)], 0);
+        assert_eq!(hits[Vec2i::new(0, 3)], 2);
+        assert_eq!(hits[Vec2i::new(1, 2)], 1);
+
+        let graph = BeamGraph::new(&grid);
+        let inner = grid
+            .pos_iter()
+            .flat_map(|(pos, _)| Direction::VALUES.map(|dir| (pos, dir)));
+        for initial in edge_entries(&grid).chain(inner) {
+            assert_eq!(graph.energized(&initial), simulate(&grid, &initial));
+        }
+
+        let frame = beam_frames(&grid, &(Vec2i::new(0, 2), Direction::East))
+            .last()
+            .unwrap();
+        assert_eq!(frame.render(&grid)[Vec2i::new(4, 2)], '/');
+        assert_eq!(frame.render(&grid)[Vec2i::new(3, 2)], '#');
+    }
+
+    #[test]
+    fn test_beam_hits() {
+        let grid = input_generator(INPUT);
+        let initial = (Vec2i::new(0, 0), Direction::East);
+        let hits = beam_hits(&grid, &[initial]);
+        assert_eq!(hits.iter().filter(|h| **h > 0).count(), 46);
+        assert_eq!(
+            hits.iter().sum::<usize>(),
+            propagate(&grid, &[initial]).len()
+        );
+        assert!(hits.iter().any(|h| *h > 1));
     }
 }
@@ -1,8 +1,9 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 use pathfinding::prelude::dijkstra;
+use rustc_hash::FxHashSet;
 use tinyvec::array_vec;
 
-use crate::common::{Direction, Grid, Vec2i};
+use crate::common::{Direction, Grid, Rgb, Vec2i};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct HeatLoss(u8);
@@ -21,9 +22,9 @@ fn find_shortest_path(
     end: &Vec2i,
     min_straight: u8,
     max_straight: u8,
-) -> u32 {
+) -> (Vec<Vec2i>, u32) {
     debug_assert!(min_straight <= max_straight);
-    let (_, cost) = dijkstra(
+    let (path, cost) = dijkstra(
         &(*start, Option::<(Direction, u8)>::None),
         |(pos, straight)| {
             let mut v = array_vec!([((Vec2i, Option<(Direction, u8)>), u32); 3]);
@@ -66,7 +67,30 @@ fn find_shortest_path(
         |(pos, straight)| pos == end && (straight.is_none() || straight.unwrap().1 >= min_straight),
     )
     .unwrap();
-    cost
+    (path.into_iter().map(|(pos, _)| pos).collect(), cost)
+}
+
+/// Renders the heat map in shades of red with the path of minimal heat loss in blue.
+pub fn render_shortest_path(
+    grid: &Grid<HeatLoss>,
+    min_straight: u8,
+    max_straight: u8,
+) -> Grid<Rgb> {
+    let (path, _) = find_shortest_path(
+        grid,
+        &Vec2i::new(0, 0),
+        &Vec2i::new((grid.size_x - 1) as _, (grid.size_y - 1) as _),
+        min_straight,
+        max_straight,
+    );
+    let path: FxHashSet<_> = path.into_iter().collect();
+    grid.map(|pos, heat_loss| {
+        if path.contains(&pos) {
+            Rgb(0x22, 0x55, 0xff)
+        } else {
+            Rgb::WHITE.mix(&Rgb(0xcc, 0x00, 0x00), heat_loss.0 as f64 / 9.0)
+        }
+    })
 }
 
 #[aoc_generator(day17)]
@@ -83,6 +107,7 @@ pub fn part1(input: &Grid<HeatLoss>) -> u32 {
         0,
         3,
     )
+    .1
 }
 
 #[aoc(day17, part2)]
@@ -94,6 +119,7 @@ pub fn part2(input: &Grid<HeatLoss>) -> u32 {
         4,
         10,
     )
+    .1
 }
 
 #[cfg(test)]
@@ -136,4 +162,20 @@ mod tests {
     fn test_part2_2() {
         assert_eq!(part2(&input_generator(INPUT_2)), 71);
     }
+
+    #[test]
+    fn test_render_shortest_path() {
+        let image = render_shortest_path(&input_generator(INPUT_2), 4, 10);
+        let path: Vec<_> = image
+            .pos_iter()
+            .filter(|(_, c)| **c == Rgb(0x22, 0x55, 0xff))
+            .map(|(pos, _)| pos)
+            .collect();
+        // the crucible has to turn early to avoid running straight for more than 10 blocks
+        assert_eq!(path.len(), 16);
+        assert!(path.contains(&Vec2i::new(0, 0)));
+        assert!(path.contains(&Vec2i::new(11, 4)));
+        assert!(!path.contains(&Vec2i::new(11, 0)));
+        assert_eq!(image[Vec2i::new(0, 1)], Rgb(0xcc, 0x00, 0x00));
+    }
 }
@@ -3,7 +3,7 @@ use std::str::FromStr;
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
 
-use crate::common::{parse_lines, Direction, Vec2i};
+use crate::common::{parse_lines, Direction, Grid, Rgb, Vec2i};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct DigEntry {
@@ -63,6 +63,51 @@ fn find_area(entries: &[DigEntry], f: impl Fn(&DigEntry) -> (Direction, i64)) ->
     (double_area + circumference + 2) / 2
 }
 
+/// Renders the part 1 trench with each meter painted in the color of its dig entry and the
+/// dug out lagoon interior in gray.
+pub fn render_trench(entries: &[DigEntry]) -> Grid<Rgb> {
+    let mut current = Vec2i::new(0, 0);
+    let mut trench = vec![];
+    for e in entries {
+        for _ in 0..e.amount {
+            current = e.dir.offset(&current);
+            trench.push((current, Rgb::from_hex(e.color)));
+        }
+    }
+
+    // leave a border of one tile so the outside is connected
+    let min = trench
+        .iter()
+        .fold(Vec2i::new(0, 0), |a, (pos, _)| a.inf(pos))
+        .add_scalar(-1);
+    let max = trench
+        .iter()
+        .fold(Vec2i::new(0, 0), |a, (pos, _)| a.sup(pos))
+        .add_scalar(1);
+    let size = max - min + Vec2i::new(1, 1);
+    let mut image: Grid<Option<Rgb>> = Grid::new_from_default(size.x as _, size.y as _);
+    for (pos, color) in trench {
+        image[pos - min] = Some(color);
+    }
+
+    let mut outside = Grid::new_from_default(image.size_x, image.size_y);
+    let mut q = vec![Vec2i::new(0, 0)];
+    while let Some(pos) = q.pop() {
+        if !image.in_bounds(&pos) || outside[pos] || image[pos].is_some() {
+            continue;
+        }
+
+        outside[pos] = true;
+        q.extend(Direction::VALUES.iter().map(|dir| dir.offset(&pos)));
+    }
+
+    image.map(|pos, color| match color {
+        Some(color) => *color,
+        None if outside[pos] => Rgb::WHITE,
+        None => Rgb(0xcc, 0xcc, 0xcc),
+    })
+}
+
 #[aoc(day18, part1)]
 pub fn part1(input: &[DigEntry]) -> i64 {
     find_area(input, |d| (d.dir, d.amount))
@@ -114,4 +159,13 @@ U 2 (#7a21e3)"#;
     fn test_part2() {
         assert_eq!(part2(&input_generator(INPUT)), 952408144115);
     }
+
+    #[test]
+    fn test_render_trench() {
+        let image = render_trench(&input_generator(INPUT));
+        assert_eq!((image.size_x, image.size_y), (9, 12));
+        assert_eq!(image.iter().filter(|c| **c != Rgb::WHITE).count(), 62);
+        assert_eq!(image[Vec2i::new(2, 1)], Rgb::from_hex(0x70c710));
+        assert_eq!(image[Vec2i::new(1, 1)], Rgb::from_hex(0x7a21e3));
+    }
 }
@@ -1,23 +1,22 @@
 use std::str::FromStr;
 
 use aoc_runner_derive::{aoc, aoc_generator};
-use rustc_hash::FxHashMap;
 
-use crate::common::{parse_lines, parse_split};
+use crate::common::{parse_lines, Interner};
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum RuleTarget {
     Reject,
     Accept,
-    Workflow(String),
+    Workflow(u32),
 }
 
-impl From<&str> for RuleTarget {
-    fn from(s: &str) -> Self {
+impl RuleTarget {
+    fn parse(s: &str, names: &mut Interner) -> Self {
         match s.trim() {
             "R" => Self::Reject,
             "A" => Self::Accept,
-            _ => Self::Workflow(s.to_string()),
+            name => Self::Workflow(names.intern(name)),
         }
     }
 }
@@ -115,31 +114,27 @@ impl RuleCondition {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct Rule {
     condition: Option<RuleCondition>,
     target: RuleTarget,
 }
 
-impl FromStr for Rule {
-    type Err = ();
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+impl Rule {
+    fn parse(s: &str, names: &mut Interner) -> Result<Self, ()> {
         Ok(if let Some((condition, target)) = s.split_once(':') {
             Self {
                 condition: Some(condition.trim().parse()?),
-                target: RuleTarget::from(target.trim()),
+                target: RuleTarget::parse(target, names),
             }
         } else {
             Self {
                 condition: None,
-                target: RuleTarget::from(s.trim()),
+                target: RuleTarget::parse(s, names),
             }
         })
     }
-}
 
-impl Rule {
     fn apply_to(&self, part: &Part) -> Option<&RuleTarget> {
         if self.condition.is_none() || self.condition.unwrap().matches(part) {
             Some(&self.target)
@@ -154,20 +149,19 @@ pub struct Workflow {
     rules: Vec<Rule>,
 }
 
-impl FromStr for Workflow {
-    type Err = ();
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+impl Workflow {
+    fn parse(s: &str, names: &mut Interner) -> Result<Self, ()> {
         Ok(Self {
-            rules: parse_split(
-                s.trim_matches(|c: char| c == '{' || c == '}' || c.is_whitespace()),
-                ',',
-            )?,
+            rules: s
+                .trim_matches(|c: char| c == '{' || c == '}' || c.is_whitespace())
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(|s| Rule::parse(s, names))
+                .collect::<Result<_, _>>()?,
         })
     }
-}
 
-impl Workflow {
     fn apply_to(&self, part: &Part) -> &RuleTarget {
         self.rules
             .iter()
@@ -179,32 +173,48 @@ impl Workflow {
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Workflows {
-    workflows: FxHashMap<String, Workflow>,
+    names: Interner,
+    /// indexed by workflow id
+    workflows: Vec<Workflow>,
+    start: u32,
 }
 
 impl FromStr for Workflows {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut names = Interner::new();
+        let start = names.intern("in");
+        let definitions: Vec<_> = s
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(|l| {
+                let i = l.find('{').ok_or(())?;
+                let id = names.intern(&l[..i]);
+                Ok((id, Workflow::parse(&l[i..], &mut names)?))
+            })
+            .collect::<Result<_, _>>()?;
+
+        let mut workflows = vec![None; names.len()];
+        for (id, workflow) in definitions {
+            workflows[id as usize] = Some(workflow);
+        }
+
         Ok(Self {
-            workflows: s
-                .lines()
-                .map(str::trim)
-                .filter(|l| !l.is_empty())
-                .map(|l| {
-                    let i = l.find('{').ok_or(())?;
-                    Ok((l[..i].to_string(), l[i..].parse()?))
-                })
-                .collect::<Result<_, _>>()?,
+            names,
+            // every referenced workflow needs to be defined
+            workflows: workflows.into_iter().collect::<Option<_>>().ok_or(())?,
+            start,
         })
     }
 }
 
 impl Workflows {
     fn accept(&self, part: &Part) -> bool {
-        let mut current = "in";
+        let mut current = self.start;
         loop {
-            match self.workflows[current].apply_to(part) {
+            match self.workflows[current as usize].apply_to(part) {
                 RuleTarget::Reject => {
                     return false;
                 }
@@ -212,7 +222,7 @@ impl Workflows {
                     return true;
                 }
                 RuleTarget::Workflow(next) => {
-                    current = next.as_str();
+                    current = *next;
                 }
             }
         }
@@ -348,7 +358,7 @@ pub fn part2((workflows, _): &(Workflows, Vec<Part>)) -> i64 {
 
     let mut accepted = 0;
     let mut q = vec![(
-        "in",
+        workflows.start,
         Ranges {
             x: (1, 4001),
             m: (1, 4001),
@@ -356,12 +366,12 @@ pub fn part2((workflows, _): &(Workflows, Vec<Part>)) -> i64 {
             s: (1, 4001),
         },
     )];
-    'outer: while let Some((name, ranges)) = q.pop() {
+    'outer: while let Some((id, ranges)) = q.pop() {
         if ranges.volume() == 0 {
             continue;
         }
 
-        let workflow = &workflows.workflows[name];
+        let workflow = &workflows.workflows[id as usize];
         let mut current_ranges = ranges;
         for rule in &workflow.rules {
             let (a, b) = current_ranges.split(&rule.condition);
@@ -369,7 +379,7 @@ pub fn part2((workflows, _): &(Workflows, Vec<Part>)) -> i64 {
             match &rule.target {
                 RuleTarget::Reject => {}
                 RuleTarget::Accept => accepted += a.volume(),
-                RuleTarget::Workflow(name) => q.push((name.as_str(), a)),
+                RuleTarget::Workflow(next) => q.push((*next, a)),
             }
 
             if current_ranges.volume() == 0 {
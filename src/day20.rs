@@ -1,21 +1,21 @@
-use std::collections::VecDeque;
+use std::fmt::Write;
 
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
-use num::Integer;
+use num::{BigUint, Integer};
 use rustc_hash::FxHashMap;
 
-use crate::common::parse_split;
+use crate::common::{CheckedInteger, Dot, Interner, OverflowError};
 
 #[derive(Debug, Clone)]
 pub enum ModuleType {
     FlipFlop(bool),
-    Conjunction(FxHashMap<String, bool>),
+    Conjunction(FxHashMap<u32, bool>),
     Broadcast,
 }
 
 impl ModuleType {
-    fn receive_pulse(&mut self, source: &str, pulse: bool) -> Option<bool> {
+    fn receive_pulse(&mut self, source: u32, pulse: bool) -> Option<bool> {
         match self {
             Self::FlipFlop(state) => {
                 if pulse {
@@ -27,7 +27,7 @@ impl ModuleType {
                 }
             }
             Self::Conjunction(state) => {
-                *state.get_mut(source).unwrap() = pulse;
+                *state.get_mut(&source).unwrap() = pulse;
                 if state.values().all(|v| *v) {
                     Some(false)
                 } else {
@@ -42,12 +42,170 @@ impl ModuleType {
 #[derive(Debug, Clone)]
 pub struct ModuleConfiguration {
     module_type: ModuleType,
-    outputs: Vec<String>,
+    outputs: Vec<u32>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Pulse {
+    pub source: u32,
+    pub target: u32,
+    pub high: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Modules {
+    names: Interner,
+    /// indexed by module id, `None` for names that only ever receive pulses
+    modules: Vec<Option<ModuleConfiguration>>,
+    button: u32,
+    broadcaster: u32,
+}
+
+impl Modules {
+    fn get_mut(&mut self, id: u32) -> Option<&mut ModuleConfiguration> {
+        self.modules[id as usize].as_mut()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (u32, &ModuleConfiguration)> {
+        self.modules
+            .iter()
+            .enumerate()
+            .filter_map(|(id, m)| m.as_ref().map(|m| (id as u32, m)))
+    }
+
+    pub fn name(&self, id: u32) -> &str {
+        self.names.name(id)
+    }
+
+    /// Presses the button once and appends every pulse sent, in processing order, to `pulses`.
+    pub fn press_button(&mut self, pulses: &mut Vec<Pulse>) {
+        let mut i = pulses.len();
+        pulses.push(Pulse {
+            source: self.button,
+            target: self.broadcaster,
+            high: false,
+        });
+        // the pulses are handled in the order they are sent, so the vec doubles as the queue
+        while let Some(&Pulse {
+            source,
+            target,
+            high,
+        }) = pulses.get(i)
+        {
+            if let Some(m) = self.get_mut(target) {
+                if let Some(new_pulse) = m.module_type.receive_pulse(source, high) {
+                    pulses.extend(m.outputs.iter().map(|&out| Pulse {
+                        source: target,
+                        target: out,
+                        high: new_pulse,
+                    }));
+                }
+            }
+            i += 1;
+        }
+    }
+
+    /// Endless iterator of button presses starting from the current state.
+    pub fn button_presses(self) -> ButtonPresses {
+        ButtonPresses { state: self }
+    }
+
+    /// One line per module with its current memory.
+    pub fn render_state(&self) -> String {
+        let mut s = String::new();
+        for (id, m) in self.iter() {
+            let name = self.names.name(id);
+            match &m.module_type {
+                ModuleType::FlipFlop(state) => {
+                    writeln!(s, "%{name}: {}", if *state { "on" } else { "off" }).unwrap()
+                }
+                ModuleType::Conjunction(state) => writeln!(
+                    s,
+                    "&{name}: {}",
+                    state
+                        .iter()
+                        .sorted_unstable_by_key(|(&input, _)| input)
+                        .map(|(&input, &high)| format!(
+                            "{}={}",
+                            self.names.name(input),
+                            if high { "high" } else { "low" }
+                        ))
+                        .join(" ")
+                )
+                .unwrap(),
+                ModuleType::Broadcast => writeln!(s, "{name}").unwrap(),
+            }
+        }
+        s
+    }
+
+    /// Renders the module network: flip-flops as boxes, conjunctions as inverted trapezia and the
+    /// broadcaster as an octagon, with the button feeding into the broadcaster.
+    pub fn to_dot(&self) -> Dot {
+        let mut dot = Dot::new(true);
+        for (id, name) in self.names.iter() {
+            let (label, shape) = match &self.modules[id as usize] {
+                Some(ModuleConfiguration {
+                    module_type: ModuleType::FlipFlop(_),
+                    ..
+                }) => (format!("%{name}"), "box"),
+                Some(ModuleConfiguration {
+                    module_type: ModuleType::Conjunction(_),
+                    ..
+                }) => (format!("&{name}"), "invtrapezium"),
+                Some(ModuleConfiguration {
+                    module_type: ModuleType::Broadcast,
+                    ..
+                }) => (name.to_string(), "octagon"),
+                None => (name.to_string(), "ellipse"),
+            };
+            dot.node(name, &[("label", &label), ("shape", shape)]);
+        }
+
+        dot.edge(
+            self.names.name(self.button),
+            self.names.name(self.broadcaster),
+            &[("style", "dashed")],
+        );
+        for (id, m) in self.iter() {
+            for &out in &m.outputs {
+                dot.edge(self.names.name(id), self.names.name(out), &[]);
+            }
+        }
+
+        dot
+    }
+}
+
+/// Iterator over the pulses sent by each button press, see [`Modules::button_presses`].
+#[derive(Debug, Clone)]
+pub struct ButtonPresses {
+    state: Modules,
+}
+
+impl ButtonPresses {
+    /// Module states after the last press.
+    pub fn state(&self) -> &Modules {
+        &self.state
+    }
+}
+
+impl Iterator for ButtonPresses {
+    type Item = Vec<Pulse>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut pulses = vec![];
+        self.state.press_button(&mut pulses);
+        Some(pulses)
+    }
 }
 
 #[aoc_generator(day20)]
-pub fn input_generator(input: &str) -> FxHashMap<String, ModuleConfiguration> {
-    let mut modules: FxHashMap<String, ModuleConfiguration> = input
+pub fn input_generator(input: &str) -> Modules {
+    let mut names = Interner::new();
+    let button = names.intern("button");
+    let broadcaster = names.intern("broadcaster");
+    let definitions: Vec<_> = input
         .lines()
         .map(str::trim)
         .filter(|l| !l.is_empty())
@@ -66,34 +224,44 @@ pub fn input_generator(input: &str) -> FxHashMap<String, ModuleConfiguration> {
                 _ => ModuleType::Broadcast,
             };
             Ok((
-                name.to_string(),
+                names.intern(name),
                 ModuleConfiguration {
                     module_type,
-                    outputs: parse_split(outputs, ',').map_err(|_| ())?,
+                    outputs: outputs
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                        .map(|s| names.intern(s))
+                        .collect(),
                 },
             ))
         })
         .collect::<Result<_, ()>>()
         .unwrap();
 
-    let conjunctions: Vec<_> = modules
+    let mut modules = vec![None; names.len()];
+    for (id, m) in definitions {
+        modules[id as usize] = Some(m);
+    }
+
+    let mut modules = Modules {
+        names,
+        modules,
+        button,
+        broadcaster,
+    };
+
+    let edges: Vec<_> = modules
         .iter()
-        .filter(|(_, m)| matches!(m.module_type, ModuleType::Conjunction(_)))
-        .map(|(name, _)| name.to_string())
+        .flat_map(|(id, m)| m.outputs.iter().map(move |&out| (id, out)))
         .collect();
-    for conjunction in &conjunctions {
-        let inputs: Vec<_> = modules
-            .iter()
-            .filter(|(_, m)| m.outputs.contains(conjunction))
-            .map(|(name, _)| name.to_string())
-            .collect();
-        match &mut modules.get_mut(conjunction).unwrap().module_type {
-            ModuleType::Conjunction(state) => {
-                for input in inputs {
-                    state.insert(input, false);
-                }
-            }
-            _ => unreachable!(),
+    for (input, conjunction) in edges {
+        if let Some(ModuleConfiguration {
+            module_type: ModuleType::Conjunction(state),
+            ..
+        }) = modules.get_mut(conjunction)
+        {
+            state.insert(input, false);
         }
     }
 
@@ -101,106 +269,112 @@ pub fn input_generator(input: &str) -> FxHashMap<String, ModuleConfiguration> {
 }
 
 #[aoc(day20, part1)]
-pub fn part1(modules: &FxHashMap<String, ModuleConfiguration>) -> i64 {
+pub fn part1(modules: &Modules) -> Result<i64, OverflowError> {
     const AMOUNT: i64 = 1000;
 
     let mut modules = modules.clone();
     let mut low = 0;
     let mut high = 0;
     let mut i = 0;
-    let mut q = VecDeque::new();
+    let mut pulses = vec![];
     loop {
         if i >= AMOUNT {
             break;
         }
 
-        q.push_back(("button".to_string(), "broadcaster".to_string(), false));
-        while let Some((source, target, pulse)) = q.pop_front() {
-            if pulse {
-                high += 1;
+        pulses.clear();
+        modules.press_button(&mut pulses);
+        for p in &pulses {
+            if p.high {
+                high = high.try_add(&1)?;
             } else {
-                low += 1;
-            }
-
-            if let Some(m) = modules.get_mut(&target) {
-                if let Some(new_pulse) = m.module_type.receive_pulse(&source, pulse) {
-                    for out in &m.outputs {
-                        q.push_back((target.to_string(), out.to_string(), new_pulse));
-                    }
-                }
+                low = low.try_add(&1)?;
             }
         }
 
         i += 1;
-        if modules.values().all(|m| match &m.module_type {
+        if modules.iter().all(|(_, m)| match &m.module_type {
             ModuleType::FlipFlop(state) => !state,
             ModuleType::Conjunction(state) => state.values().all(|s| !s),
             ModuleType::Broadcast => true,
         }) {
             let cycles = AMOUNT / i;
-            low *= cycles;
-            high *= cycles;
-            i *= cycles;
+            low = low.try_mul(&cycles)?;
+            high = high.try_mul(&cycles)?;
+            i = i.try_mul(&cycles)?;
         }
     }
 
-    low * high
+    low.try_mul(&high)
 }
 
-#[aoc(day20, part2)]
-pub fn part2(modules: &FxHashMap<String, ModuleConfiguration>) -> u64 {
+fn rx_input_periods(modules: &Modules) -> Vec<u64> {
     fn get_button_presses_until(
-        mut modules: FxHashMap<String, ModuleConfiguration>,
-        expected_source: &str,
-        expected_target: &str,
+        mut modules: Modules,
+        expected_source: u32,
+        expected_target: u32,
         expected_pulse: bool,
     ) -> u64 {
-        let mut i = 0;
-        let mut q = VecDeque::new();
+        let expected = Pulse {
+            source: expected_source,
+            target: expected_target,
+            high: expected_pulse,
+        };
+        let mut pulses = vec![];
+        let mut presses = 0;
         loop {
-            i += 1;
-            q.push_back(("button".to_string(), "broadcaster".to_string(), false));
-            while let Some((source, target, pulse)) = q.pop_front() {
-                if pulse == expected_pulse && source == expected_source && target == expected_target
-                {
-                    return i;
-                }
-
-                if let Some(m) = modules.get_mut(&target) {
-                    if let Some(new_pulse) = m.module_type.receive_pulse(&source, pulse) {
-                        for out in &m.outputs {
-                            q.push_back((target.to_string(), out.to_string(), new_pulse));
-                        }
-                    }
-                }
+            pulses.clear();
+            modules.press_button(&mut pulses);
+            presses += 1;
+            if pulses.contains(&expected) {
+                return presses;
             }
         }
     }
 
-    let input = modules
+    let rx = modules.names.get("rx").unwrap();
+    let Ok(input) = modules
         .iter()
-        .filter(|(_, m)| m.outputs.iter().any(|o| o == "rx"))
-        .map(|(name, _)| name.to_string())
+        .filter(|(_, m)| m.outputs.contains(&rx))
+        .map(|(id, _)| id)
         .exactly_one()
-        .unwrap();
-    let conj_inputs: Vec<_> =
-        if let ModuleType::Conjunction(conj_state) = &modules[&input].module_type {
-            conj_state.keys().map(|name| name.to_string()).collect()
-        } else {
-            panic!("assume: {input} is conjunction");
-        };
-    println!("{conj_inputs:?} -> &{input} -> rx");
-
+    else {
+        panic!("assume: exactly one module sends pulses to rx");
+    };
+    let input_name = modules.names.name(input);
+    let conj_inputs: Vec<_> = if let Some(ModuleConfiguration {
+        module_type: ModuleType::Conjunction(conj_state),
+        ..
+    }) = &modules.modules[input as usize]
+    {
+        conj_state.keys().copied().collect()
+    } else {
+        panic!("assume: {input_name} is conjunction");
+    };
     // assume looping inputs
     // assume lots of low pulses and then exactly one high pulse
-    let mut result = 1;
-    for i in &conj_inputs {
-        let n = get_button_presses_until(modules.clone(), i.as_str(), input.as_str(), true);
-        println!("{n} button presses until {i} sends high pulse to {input}");
-        result = result.lcm(&n);
-    }
+    conj_inputs
+        .iter()
+        .map(|&i| get_button_presses_until(modules.clone(), i, input, true))
+        .collect()
+}
+
+fn checked_lcm<T: CheckedInteger + Integer>(
+    periods: impl IntoIterator<Item = T>,
+) -> Result<T, OverflowError> {
+    periods
+        .into_iter()
+        .try_fold(T::one(), |acc, n| (acc.clone() / acc.gcd(&n)).try_mul(&n))
+}
+
+#[aoc(day20, part2)]
+pub fn part2(modules: &Modules) -> Result<u64, OverflowError> {
+    checked_lcm(rx_input_periods(modules))
+}
 
-    result
+#[aoc(day20, part2, bigint)]
+pub fn part2_bigint(modules: &Modules) -> Result<BigUint, OverflowError> {
+    checked_lcm(rx_input_periods(modules).into_iter().map(BigUint::from))
 }
 
 #[cfg(test)]
@@ -223,11 +397,56 @@ mod tests {
 
     #[test]
     fn test_part1_1() {
-        assert_eq!(part1(&input_generator(INPUT_1)), 32000000);
+        assert_eq!(part1(&input_generator(INPUT_1)), Ok(32000000));
     }
 
     #[test]
     fn test_part1_2() {
-        assert_eq!(part1(&input_generator(INPUT_2)), 11687500);
+        assert_eq!(part1(&input_generator(INPUT_2)), Ok(11687500));
+    }
+
+    #[test]
+    fn test_checked_lcm() {
+        let periods = [4091u64, 3917, 3919, 4027, 4001, 3989];
+        assert_eq!(checked_lcm(periods), Err(OverflowError));
+        assert_eq!(
+            checked_lcm(periods.map(BigUint::from)),
+            Ok(periods.iter().map(|&p| BigUint::from(p)).product())
+        );
+    }
+
+    #[test]
+    fn test_to_dot() {
+        assert_eq!(
+            input_generator(INPUT_2).to_dot().to_string(),
+            r#"digraph {
+    "button" [label="button", shape="ellipse"];
+    "broadcaster" [label="broadcaster", shape="octagon"];
+    "a" [label="%a", shape="box"];
+    "inv" [label="&inv", shape="invtrapezium"];
+    "con" [label="&con", shape="invtrapezium"];
+    "b" [label="%b", shape="box"];
+    "output" [label="output", shape="ellipse"];
+    "button" -> "broadcaster" [style="dashed"];
+    "broadcaster" -> "a";
+    "a" -> "inv";
+    "a" -> "con";
+    "inv" -> "b";
+    "con" -> "output";
+    "b" -> "con";
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_button_presses() {
+        let mut presses = input_generator(INPUT_2).button_presses();
+        assert_eq!(presses.next().map(|p| p.len()), Some(8));
+        assert_eq!(
+            presses.state().render_state(),
+            "broadcaster\n%a: on\n&inv: a=high\n&con: a=high b=high\n%b: on\n"
+        );
+        assert_eq!(presses.take(3).map(|p| p.len()).collect_vec(), [6, 8, 6]);
     }
 }
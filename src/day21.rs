@@ -25,6 +25,16 @@ impl TryFrom<char> for Tile {
     }
 }
 
+impl From<Tile> for char {
+    fn from(value: Tile) -> Self {
+        match value {
+            Tile::Empty => '.',
+            Tile::Obstacle => '#',
+            Tile::Start => 'S',
+        }
+    }
+}
+
 fn replace_start(pipes: &mut Grid<Tile>) -> Vec2i {
     let start = pipes
         .pos_iter()
@@ -35,22 +45,50 @@ fn replace_start(pipes: &mut Grid<Tile>) -> Vec2i {
     start
 }
 
+fn step(grid: &Grid<Tile>, current: &FxHashSet<Vec2i>, next: &mut FxHashSet<Vec2i>) {
+    next.clear();
+    next.extend(
+        current
+            .iter()
+            .flat_map(|v| Direction::VALUES.iter().map(|d| d.offset(v)))
+            .filter(|v| *grid.mod_get(v) != Tile::Obstacle),
+    );
+}
+
 fn get_reachable(grid: &Grid<Tile>, start: Vec2i, steps: usize) -> usize {
     let mut current: FxHashSet<Vec2i> = FxHashSet::from_iter([start]);
     let mut next: FxHashSet<Vec2i> = FxHashSet::default();
     for _ in 0..steps {
-        next.clear();
-        next.extend(
-            current
-                .iter()
-                .flat_map(|v| Direction::VALUES.iter().map(|d| d.offset(v)))
-                .filter(|v| *grid.mod_get(v) != Tile::Obstacle),
-        );
+        step(grid, &current, &mut next);
         std::mem::swap(&mut current, &mut next);
     }
     current.len()
 }
 
+/// Endless iterator of the plots reachable in exactly 0, 1, 2, ... steps on the infinitely
+/// repeating garden.
+pub fn garden_steps(
+    grid: &Grid<Tile>,
+    start: Vec2i,
+) -> impl Iterator<Item = FxHashSet<Vec2i>> + '_ {
+    std::iter::successors(Some(FxHashSet::from_iter([start])), move |current| {
+        let mut next = FxHashSet::default();
+        step(grid, current, &mut next);
+        Some(next)
+    })
+}
+
+/// Marks the reachable plots inside the original garden with `O`.
+pub fn render_reachable(grid: &Grid<Tile>, reachable: &FxHashSet<Vec2i>) -> Grid<char> {
+    grid.map(|pos, t| {
+        if reachable.contains(&pos) {
+            'O'
+        } else {
+            char::from(*t)
+        }
+    })
+}
+
 #[aoc_generator(day21)]
 pub fn input_generator(input: &str) -> (Vec2i, Grid<Tile>) {
     let mut grid = input.parse().unwrap();
@@ -107,4 +145,21 @@ mod tests {
         let (start, grid) = input_generator(INPUT);
         assert_eq!(get_reachable(&grid, start, 6), 16);
     }
+
+    #[test]
+    fn test_garden_steps() {
+        let (start, grid) = input_generator(INPUT);
+        let steps: Vec<_> = garden_steps(&grid, start).take(7).collect();
+        assert_eq!(
+            steps.iter().map(|s| s.len()).collect::<Vec<_>>(),
+            [1, 2, 4, 6, 9, 13, 16]
+        );
+        assert_eq!(
+            render_reachable(&grid, &steps[1])
+                .to_string()
+                .lines()
+                .nth(4),
+            Some("....#O#....")
+        );
+    }
 }
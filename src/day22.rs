@@ -1,9 +1,10 @@
 use std::str::FromStr;
 
 use aoc_runner_derive::{aoc, aoc_generator};
+use itertools::Itertools;
 use rustc_hash::{FxHashMap, FxHashSet};
 
-use crate::common::{parse_lines, parse_vec, Vec2i, Vec3i};
+use crate::common::{parse_lines, parse_vec, Dot, Vec2i, Vec3i};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct Brick {
@@ -50,16 +51,14 @@ pub fn input_generator(input: &str) -> Vec<Brick> {
 
 const FLOOR: i64 = 0;
 
+type BrickRelation = FxHashMap<usize, FxHashSet<usize>>;
+
 fn simulate_bricks(
     bricks: &[Brick],
-) -> (
-    FxHashMap<Vec2i, (i64, usize)>,
-    FxHashMap<usize, FxHashSet<usize>>,
-    FxHashMap<usize, FxHashSet<usize>>,
-) {
+) -> (FxHashMap<Vec2i, (i64, usize)>, BrickRelation, BrickRelation) {
     let mut heightmap: FxHashMap<Vec2i, (i64, usize)> = FxHashMap::default();
-    let mut supporting: FxHashMap<usize, FxHashSet<usize>> = FxHashMap::default();
-    let mut supported_by: FxHashMap<usize, FxHashSet<usize>> = FxHashMap::default();
+    let mut supporting: BrickRelation = FxHashMap::default();
+    let mut supported_by: BrickRelation = FxHashMap::default();
 
     for (i, b) in bricks.iter().enumerate() {
         debug_assert!(b.min.z > FLOOR);
@@ -96,27 +95,54 @@ fn simulate_bricks(
     (heightmap, supporting, supported_by)
 }
 
-#[aoc(day22, part1)]
-pub fn part1(bricks: &[Brick]) -> usize {
+fn can_disintegrate(i: usize, supporting: &BrickRelation, supported_by: &BrickRelation) -> bool {
+    let Some(on_top) = supporting.get(&i) else {
+        return true;
+    };
+
+    on_top.iter().all(|top_index| {
+        let below = &supported_by[top_index];
+        below.iter().any(|below_index| *below_index != i)
+    })
+}
+
+/// Renders which brick rests on which after settling, with the supporting brick below.
+/// Bricks that can be safely disintegrated are filled.
+pub fn support_to_dot(bricks: &[Brick]) -> Dot {
     let (_heightmap, supporting, supported_by) = simulate_bricks(bricks);
 
-    let mut count = 0;
+    let mut dot = Dot::new(true);
+    dot.graph_attributes(&[("rankdir", "BT")]);
+    for (i, b) in bricks.iter().enumerate() {
+        let label = format!(
+            "{i}: {},{},{}~{},{},{}",
+            b.min.x, b.min.y, b.min.z, b.max.x, b.max.y, b.max.z
+        );
+        if can_disintegrate(i, &supporting, &supported_by) {
+            dot.node(&i.to_string(), &[("label", &label), ("style", "filled")]);
+        } else {
+            dot.node(&i.to_string(), &[("label", &label)]);
+        }
+    }
+
     for i in 0..bricks.len() {
-        let Some(on_top) = supporting.get(&i) else {
-            count += 1;
-            continue;
-        };
-
-        if on_top.iter().all(|top_index| {
-            let below = &supported_by[top_index];
-            below.iter().any(|below_index| *below_index != i)
-        }) {
-            count += 1;
-            continue;
+        if let Some(on_top) = supporting.get(&i) {
+            for top_index in on_top.iter().sorted() {
+                dot.edge(&i.to_string(), &top_index.to_string(), &[]);
+            }
         }
     }
 
-    count
+    dot
+}
+
+#[aoc(day22, part1)]
+pub fn part1(bricks: &[Brick]) -> usize {
+    let (_heightmap, supporting, supported_by) = simulate_bricks(bricks);
+
+    (0..bricks.len())
+        .filter(|&i| can_disintegrate(i, &supporting, &supported_by))
+        .count()
 }
 
 #[aoc(day22, part2)]
@@ -173,4 +199,31 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(&input_generator(INPUT)), 7);
     }
+
+    #[test]
+    fn test_support_to_dot() {
+        assert_eq!(
+            support_to_dot(&input_generator(INPUT)).to_string(),
+            r#"digraph {
+    graph [rankdir="BT"];
+    "0" [label="0: 1,0,1~1,2,1"];
+    "1" [label="1: 0,0,2~2,0,2", style="filled"];
+    "2" [label="2: 0,2,3~2,2,3", style="filled"];
+    "3" [label="3: 0,0,4~0,2,4", style="filled"];
+    "4" [label="4: 2,0,5~2,2,5", style="filled"];
+    "5" [label="5: 0,1,6~2,1,6"];
+    "6" [label="6: 1,1,8~1,1,9", style="filled"];
+    "0" -> "1";
+    "0" -> "2";
+    "1" -> "3";
+    "1" -> "4";
+    "2" -> "3";
+    "2" -> "4";
+    "3" -> "5";
+    "4" -> "5";
+    "5" -> "6";
+}
+"#
+        );
+    }
 }
@@ -2,9 +2,10 @@ use std::hash::BuildHasherDefault;
 
 use aoc_runner_derive::{aoc, aoc_generator};
 use indexmap::IndexSet;
+use itertools::Itertools;
 use rustc_hash::{FxHashMap, FxHasher};
 
-use crate::common::{Direction, Grid, Vec2i};
+use crate::common::{Direction, Dot, Grid, Vec2i};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum Tile {
@@ -49,76 +50,76 @@ pub fn input_generator(input: &str) -> Grid<Tile> {
     input.parse().unwrap()
 }
 
-fn longest_path(grid: &Grid<Tile>, ignore_slopes: bool) -> usize {
-    fn build_crossing_graph(
-        grid: &Grid<Tile>,
-        start: Vec2i,
-        end: Vec2i,
-        ignore_slopes: bool,
-    ) -> FxHashMap<Vec2i, Vec<(Vec2i, usize)>> {
-        let mut graph: FxHashMap<Vec2i, Vec<(Vec2i, usize)>> = Default::default();
-        let mut q = vec![start];
-        while let Some(pos) = q.pop() {
-            if pos == end || graph.contains_key(&pos) {
+fn build_crossing_graph(
+    grid: &Grid<Tile>,
+    start: Vec2i,
+    end: Vec2i,
+    ignore_slopes: bool,
+) -> FxHashMap<Vec2i, Vec<(Vec2i, usize)>> {
+    let mut graph: FxHashMap<Vec2i, Vec<(Vec2i, usize)>> = Default::default();
+    let mut q = vec![start];
+    while let Some(pos) = q.pop() {
+        if pos == end || graph.contains_key(&pos) {
+            continue;
+        }
+
+        let mut children = vec![];
+        for initial_dir in Direction::VALUES {
+            if !grid[pos].can_walk_out(&initial_dir, ignore_slopes) {
                 continue;
             }
 
-            let mut children = vec![];
-            for initial_dir in Direction::VALUES {
-                if !grid[pos].can_walk_out(&initial_dir, ignore_slopes) {
-                    continue;
-                }
+            let mut current = initial_dir.offset(&pos);
+            if !grid.in_bounds(&current) {
+                continue;
+            }
 
-                let mut current = initial_dir.offset(&pos);
-                if !grid.in_bounds(&current) {
-                    continue;
-                }
+            let mut came_from = initial_dir.opposite();
+            if !grid[current].can_walk_into() {
+                continue;
+            }
 
-                let mut came_from = initial_dir.opposite();
-                if !grid[current].can_walk_into() {
-                    continue;
-                }
+            let mut length = 1;
+            loop {
+                let mut it = Direction::VALUES
+                    .iter()
+                    .filter(|dir| {
+                        **dir != came_from && grid[current].can_walk_out(dir, ignore_slopes)
+                    })
+                    .map(|dir| (dir.offset(&current), *dir))
+                    .filter(|(offset_pos, _)| {
+                        grid.in_bounds(offset_pos) && grid[*offset_pos].can_walk_into()
+                    });
 
-                let mut length = 1;
-                loop {
-                    let mut it = Direction::VALUES
-                        .iter()
-                        .filter(|dir| {
-                            **dir != came_from && grid[current].can_walk_out(dir, ignore_slopes)
-                        })
-                        .map(|dir| (dir.offset(&current), *dir))
-                        .filter(|(offset_pos, _)| {
-                            grid.in_bounds(offset_pos) && grid[*offset_pos].can_walk_into()
-                        });
-
-                    if let Some((neighbor, dir)) = it.next() {
-                        if it.next().is_none() {
-                            current = neighbor;
-                            came_from = dir.opposite();
-                            length += 1;
-                        } else {
-                            // crossing
-                            children.push((current, length));
-                            q.push(current);
-                            break;
-                        }
+                if let Some((neighbor, dir)) = it.next() {
+                    if it.next().is_none() {
+                        current = neighbor;
+                        came_from = dir.opposite();
+                        length += 1;
                     } else {
-                        // no children
-                        if current == end {
-                            children.push((current, length));
-                            q.push(current);
-                        }
-
+                        // crossing
+                        children.push((current, length));
+                        q.push(current);
                         break;
                     }
+                } else {
+                    // no children
+                    if current == end {
+                        children.push((current, length));
+                        q.push(current);
+                    }
+
+                    break;
                 }
             }
-
-            graph.insert(pos, children);
         }
-        graph
+
+        graph.insert(pos, children);
     }
+    graph
+}
 
+fn find_start_end(grid: &Grid<Tile>) -> (Vec2i, Vec2i) {
     let (start, _) = grid
         .pos_iter_row(0)
         .find(|(_, t)| **t == Tile::Path)
@@ -127,6 +128,45 @@ fn longest_path(grid: &Grid<Tile>, ignore_slopes: bool) -> usize {
         .pos_iter_row((grid.size_y - 1) as i64)
         .find(|(_, t)| **t == Tile::Path)
         .unwrap();
+    (start, end)
+}
+
+/// Renders the junction graph used for the longest path search, with edges weighted by
+/// their length in steps. When slopes are ignored every edge is walkable in both directions.
+pub fn crossing_graph_to_dot(grid: &Grid<Tile>, ignore_slopes: bool) -> Dot {
+    fn node_id(pos: &Vec2i) -> String {
+        format!("{},{}", pos.x, pos.y)
+    }
+
+    let (start, end) = find_start_end(grid);
+    let crossing_graph = build_crossing_graph(grid, start, end, ignore_slopes);
+
+    let mut dot = Dot::new(!ignore_slopes);
+    dot.node(&node_id(&start), &[("shape", "doublecircle")]);
+    dot.node(&node_id(&end), &[("shape", "doublecircle")]);
+    for (pos, children) in crossing_graph
+        .iter()
+        .sorted_by_key(|(pos, _)| (pos.y, pos.x))
+    {
+        for (child, length) in children {
+            // undirected edges show up from both sides, only keep one of them
+            if ignore_slopes && (child.y, child.x) < (pos.y, pos.x) {
+                continue;
+            }
+
+            dot.edge(
+                &node_id(pos),
+                &node_id(child),
+                &[("label", &length.to_string())],
+            );
+        }
+    }
+
+    dot
+}
+
+fn longest_path(grid: &Grid<Tile>, ignore_slopes: bool) -> usize {
+    let (start, end) = find_start_end(grid);
 
     // only keep crossings and start+end
     // assumption: |crossings| << |nodes|
@@ -204,4 +244,26 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(&input_generator(INPUT)), 154);
     }
+
+    #[test]
+    fn test_crossing_graph_to_dot() {
+        const INPUT: &str = r#"#.###
+#...#
+#.#.#
+#...#
+###.#"#;
+
+        assert_eq!(
+            crossing_graph_to_dot(&input_generator(INPUT), true).to_string(),
+            r#"graph {
+    "1,0" [shape="doublecircle"];
+    "3,4" [shape="doublecircle"];
+    "1,0" -- "1,1" [label="1"];
+    "1,1" -- "3,3" [label="4"];
+    "1,1" -- "3,3" [label="4"];
+    "3,3" -- "3,4" [label="1"];
+}
+"#
+        );
+    }
 }
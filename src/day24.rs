@@ -377,7 +377,7 @@ pub fn part2(hailstones: &[Hailstone]) -> Rational128 {
 
             // find two points on the plane so we can find the rock trajectory
             // that trajectory has to go through these points
-            let Some((c, d)) = hailstones
+            let (c, d) = hailstones
                 .iter()
                 .filter_map(|h| {
                     let q = convert(&h.pos);
@@ -393,10 +393,7 @@ pub fn part2(hailstones: &[Hailstone]) -> Rational128 {
                     Some(plane_intersect)
                 })
                 .take(2)
-                .collect_tuple()
-            else {
-                return None;
-            };
+                .collect_tuple()?;
 
             let r = c;
             let w = d - c;
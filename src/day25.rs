@@ -5,7 +5,7 @@ use itertools::Itertools;
 use pathfinding::prelude::*;
 use rustc_hash::{FxHashMap, FxHashSet};
 
-use crate::common::parse_lines;
+use crate::common::{parse_lines, Dot, Interner};
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct EdgeInfo {
@@ -30,40 +30,54 @@ pub fn input_generator(input: &str) -> Vec<EdgeInfo> {
     parse_lines(input).unwrap()
 }
 
-fn build_graph(edge_infos: &[EdgeInfo]) -> FxHashMap<&str, FxHashSet<&str>> {
-    let mut graph: FxHashMap<&str, FxHashSet<&str>> = FxHashMap::default();
-    for e in edge_infos {
-        for t in &e.targets {
-            graph
-                .entry(e.source.as_str())
-                .or_default()
-                .insert(t.as_str());
-            graph
-                .entry(t.as_str())
-                .or_default()
-                .insert(e.source.as_str());
+/// Undirected graph with neighbours indexed by vertex id.
+#[derive(Debug, Clone)]
+struct Graph {
+    names: Interner,
+    neighbours: Vec<Vec<u32>>,
+}
+
+impl Graph {
+    fn remove_edge(&mut self, a: u32, b: u32) {
+        self.neighbours[a as usize].retain(|&n| n != b);
+        self.neighbours[b as usize].retain(|&n| n != a);
+    }
+}
+
+fn build_graph(edge_infos: &[EdgeInfo]) -> Graph {
+    let mut names = Interner::new();
+    let edges: Vec<_> = edge_infos
+        .iter()
+        .flat_map(|e| e.targets.iter().map(move |t| (&e.source, t)))
+        .map(|(source, target)| (names.intern(source), names.intern(target)))
+        .collect();
+
+    let mut neighbours = vec![Vec::new(); names.len()];
+    for (a, b) in edges {
+        if !neighbours[a as usize].contains(&b) {
+            neighbours[a as usize].push(b);
+            neighbours[b as usize].push(a);
         }
     }
 
-    graph
+    Graph { names, neighbours }
 }
 
-fn find_cut<'a>(
-    graph: &FxHashMap<&'a str, FxHashSet<&'a str>>,
-    k: usize,
-) -> FxHashSet<(&'a str, &'a str)> {
-    let mut edge_counter: FxHashMap<(&str, &str), usize> = FxHashMap::default();
-    let mut seen_keys = FxHashSet::default();
-    for v in graph.keys() {
-        seen_keys.insert(*v);
-        let reachable = dijkstra_all(v, |&n| graph[n].iter().map(|c| (*c, 1usize)));
+fn find_cut(graph: &Graph, k: usize) -> FxHashSet<(u32, u32)> {
+    let mut edge_counter: FxHashMap<(u32, u32), usize> = FxHashMap::default();
+    let mut seen_keys = vec![false; graph.names.len()];
+    for (v, _) in graph.names.iter() {
+        seen_keys[v as usize] = true;
+        let reachable = dijkstra_all(&v, |&n| {
+            graph.neighbours[n as usize].iter().map(|&c| (c, 1usize))
+        });
         for &target in reachable.keys() {
-            if seen_keys.contains(target) {
+            if seen_keys[target as usize] {
                 continue;
             }
 
             let mut next = target;
-            while let Some(&(parent, _)) = reachable.get(next) {
+            while let Some(&(parent, _)) = reachable.get(&next) {
                 let edge = if next < parent {
                     (next, parent)
                 } else {
@@ -83,17 +97,46 @@ fn find_cut<'a>(
         .collect()
 }
 
+/// Renders the component graph with the edges of the found 3-cut highlighted.
+pub fn to_dot(edges: &[EdgeInfo]) -> Dot {
+    let graph = build_graph(edges);
+    let cut = find_cut(&graph, 3);
+
+    let mut dot = Dot::new(false);
+    for (v, name) in graph.names.iter() {
+        dot.node(name, &[]);
+        for &n in &graph.neighbours[v as usize] {
+            if v > n {
+                continue;
+            }
+
+            if cut.contains(&(v, n)) {
+                dot.edge(
+                    name,
+                    graph.names.name(n),
+                    &[("color", "red"), ("penwidth", "3")],
+                );
+            } else {
+                dot.edge(name, graph.names.name(n), &[]);
+            }
+        }
+    }
+
+    dot
+}
+
 #[aoc(day25, part1)]
 pub fn part1(edges: &[EdgeInfo]) -> usize {
     let mut graph = build_graph(edges);
     let cut = find_cut(&graph, 3);
-    for (a, b) in &cut {
-        graph.get_mut(a).unwrap().remove(b);
-        graph.get_mut(b).unwrap().remove(a);
+    for &(a, b) in &cut {
+        graph.remove_edge(a, b);
     }
 
-    let all_vertices: Vec<_> = graph.keys().copied().collect();
-    let comps = connected_components(&all_vertices, |v| graph[v].iter().copied());
+    let all_vertices: Vec<_> = graph.names.iter().map(|(v, _)| v).collect();
+    let comps = connected_components(&all_vertices, |&v| {
+        graph.neighbours[v as usize].iter().copied()
+    });
 
     comps.iter().map(|comp| comp.len()).product()
 }
@@ -122,4 +165,16 @@ frs: qnr lhk lsr"#;
     fn test_part1() {
         assert_eq!(part1(&input_generator(INPUT)), 54);
     }
+
+    #[test]
+    fn test_to_dot() {
+        let dot = to_dot(&input_generator(INPUT)).to_string();
+        let cut: Vec<_> = dot
+            .lines()
+            .filter(|l| l.contains(r#"color="red""#))
+            .map(|l| l.split('"').skip(1).step_by(2).take(2).sorted().join("/"))
+            .sorted()
+            .collect();
+        assert_eq!(cut, ["bvb/cmg", "hfx/pzl", "jqt/nvd"]);
+    }
 }